@@ -0,0 +1,61 @@
+/**
+ * Splash screen shown by the Tauri shell while the backend sidecar starts.
+ * The shell calls showStartupError() if the backend never becomes ready,
+ * showStarting() when it tries again, and showExternalBackendForm() when the
+ * user chooses to connect to a backend they run themselves. With several
+ * workspaces the shell waits for one to be picked here before it starts the
+ * backend.
+ */
+
+window.showStartupError = function (message, stderrLines) {
+  document.getElementById('error-message').textContent = message;
+  document.getElementById('error-output').textContent =
+    stderrLines && stderrLines.length ? stderrLines.join('\n') : 'The backend printed no errors.';
+  document.body.classList.add('failed');
+};
+
+window.showStarting = function () {
+  document.body.classList.remove('failed', 'external', 'picking');
+};
+
+window.showExternalBackendForm = function () {
+  document.body.classList.add('external');
+  document.getElementById('external-url').focus();
+};
+
+document.getElementById('external-form').addEventListener('submit', (event) => {
+  event.preventDefault();
+  const url = document.getElementById('external-url').value.trim();
+  const apiKey = document.getElementById('external-key').value;
+  if (!url) return;
+  window.showStarting();
+  window.__TAURI__.invoke('connect_external_backend', { url, apiKey: apiKey || null });
+});
+
+function showWorkspacePicker(list) {
+  const select = document.getElementById('workspace-select');
+  select.replaceChildren(
+    ...list.workspaces.map((workspace) => {
+      const option = document.createElement('option');
+      option.value = workspace.id;
+      option.textContent = workspace.name;
+      option.selected = workspace.id === list.active;
+      return option;
+    })
+  );
+  document.body.classList.add('picking');
+  select.focus();
+}
+
+document.getElementById('workspace-form').addEventListener('submit', (event) => {
+  event.preventDefault();
+  const id = document.getElementById('workspace-select').value;
+  window.showStarting();
+  window.__TAURI__.invoke('switch_workspace', { id }).catch((error) => {
+    window.showStartupError(String(error), []);
+  });
+});
+
+window.__TAURI__.invoke('list_workspaces').then((list) => {
+  if (list.awaiting_choice) showWorkspacePicker(list);
+});
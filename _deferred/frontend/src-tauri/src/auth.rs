@@ -0,0 +1,92 @@
+//! Per-launch shared secret between the shell and the backend.
+//!
+//! The token is handed to the sidecar as its only accepted API key and is
+//! attached by the `api_request` proxy; it never leaves the Rust side.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use tauri::{AppHandle, Manager};
+
+/// Header the backend's `require_api_key` checks.
+pub const HEADER: &str = "X-API-Key";
+
+pub const EVENT_REJECTED: &str = "backend://auth-rejected";
+
+#[derive(Clone, serde::Serialize)]
+struct RejectedPayload {
+    status: u16,
+    /// Request line as logged by the backend, e.g. `POST /entry HTTP/1.1`.
+    request: String,
+}
+
+/// 256 random bits, URL-safe so it survives the backend's `key[:expiry]` parsing.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Emit `backend://auth-rejected` if a backend log message records a 401/403.
+pub fn check_access_log(app: &AppHandle, line: &str) {
+    if let Some(rejected) = rejected_request(line) {
+        log_error!(
+            "[Shell] Backend rejected unauthenticated request: {}",
+            rejected.request
+        );
+        let _ = app.emit_all(EVENT_REJECTED, rejected);
+    }
+}
+
+/// The 401/403 recorded by an access log message, if it is one.
+///
+/// Werkzeug's access log message looks like:
+/// `127.0.0.1 - - [15/Oct/2026 10:00:00] "POST /entry HTTP/1.1" 401 -`
+fn rejected_request(line: &str) -> Option<RejectedPayload> {
+    let (start, end) = (line.find('"')?, line.rfind('"')?);
+    if start >= end {
+        return None;
+    }
+    let status = line[end + 1..]
+        .split_whitespace()
+        .next()
+        .and_then(|code| code.parse::<u16>().ok());
+
+    match status {
+        Some(status @ (401 | 403)) => Some(RejectedPayload {
+            status,
+            request: line[start + 1..end].to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_rejected_requests() {
+        let line = r#"127.0.0.1 - - [15/Oct/2026 10:00:00] "POST /entry HTTP/1.1" 401 -"#;
+        let rejected = rejected_request(line).unwrap();
+        assert_eq!(rejected.status, 401);
+        assert_eq!(rejected.request, "POST /entry HTTP/1.1");
+
+        let line = r#"127.0.0.1 - - [15/Oct/2026 10:00:00] "GET /chain HTTP/1.1" 403 52"#;
+        assert_eq!(rejected_request(line).unwrap().status, 403);
+    }
+
+    #[test]
+    fn ignores_other_statuses_and_messages() {
+        for line in [
+            r#"127.0.0.1 - - [15/Oct/2026 10:00:00] "GET /health HTTP/1.1" 200 -"#,
+            r#"127.0.0.1 - - [15/Oct/2026 10:00:00] "GET /entry/401 HTTP/1.1" 404 -"#,
+            r#"127.0.0.1 - - [15/Oct/2026 10:00:00] "GET /chain HTTP/1.1" -"#,
+            r#"Entry "401" rejected"#,
+            r#"A single " quote 401"#,
+            "Running on http://127.0.0.1:5000",
+        ] {
+            assert!(rejected_request(line).is_none(), "{}", line);
+        }
+    }
+}
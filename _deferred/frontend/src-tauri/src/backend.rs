@@ -0,0 +1,338 @@
+//! Backend sidecar process management.
+//!
+//! Each workspace has its own `BackendState` (port, API key, restart
+//! history, ...), kept in `Backends` by workspace id. Only the active
+//! workspace's sidecar runs; `state` returns that one.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tauri::api::process::{Command, CommandChild, CommandEvent};
+use tauri::async_runtime::Receiver;
+use tauri::{AppHandle, Manager};
+
+use crate::logs::{self, LogRecord, Stream};
+use crate::compat::CompatReport;
+use crate::datadir::DataDir;
+use crate::options::LaunchOptions;
+use crate::proxy::Proxy;
+use crate::settings::{self, BackendSettings};
+use crate::startup::{self, StartupError};
+use crate::{auth, datadir, health, logstream, port, supervisor, workspace};
+
+pub const SIDECAR: &str = "natlangchain-backend";
+
+pub const EVENT_STATUS: &str = "backend://status";
+
+/// Number of stderr lines kept for the startup error screen.
+const STDERR_TAIL_LINES: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendStatus {
+    /// Spawned, waiting for `/health/ready`.
+    Starting,
+    Ready,
+    /// Crashed; a restart is scheduled.
+    Restarting,
+    /// Stopped on purpose by the shell.
+    Stopped,
+    /// Crashed too often; the supervisor gave up.
+    Failed,
+}
+
+pub struct BackendState {
+    /// The workspace this backend belongs to.
+    pub workspace: String,
+    pub child: Option<CommandChild>,
+    pub status: BackendStatus,
+    /// Loopback port the sidecar listens on; 0 until the first spawn.
+    pub port: u16,
+    /// Per-launch API key; only the sidecar and the `api_request` proxy know it.
+    /// In external mode, the configured key for that backend (maybe empty).
+    pub api_token: String,
+    /// Set once an external backend has been validated; no sidecar runs then.
+    pub external_url: Option<String>,
+    /// Bumped on every spawn so stale readiness pollers can tell they're stale.
+    pub generation: u64,
+    pub restart_count: u32,
+    pub last_exit_code: Option<i32>,
+    /// When each restart within the supervisor's window happened.
+    pub recent_restarts: VecDeque<Instant>,
+    /// Most recent stderr lines, oldest first.
+    pub stderr_tail: VecDeque<String>,
+    /// Result of the last version handshake.
+    pub compat: Option<CompatReport>,
+}
+
+/// Every workspace's `BackendState`, keyed by workspace id.
+#[derive(Default)]
+pub struct Backends(Mutex<HashMap<String, Arc<Mutex<BackendState>>>>);
+
+impl Backends {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Workspace `id`'s backend, created the first time it is asked for.
+    pub fn get(&self, id: &str) -> Arc<Mutex<BackendState>> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(BackendState::new(id))))
+            .clone()
+    }
+
+    /// Forget a deleted workspace's backend, which must already be stopped.
+    pub fn remove(&self, id: &str) {
+        self.0.lock().unwrap().remove(id);
+    }
+}
+
+/// The active workspace's backend.
+pub fn state(app: &AppHandle) -> Arc<Mutex<BackendState>> {
+    app.state::<Backends>().get(&workspace::active_id(app))
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct StatusSnapshot {
+    pub status: BackendStatus,
+    pub url: String,
+    pub external: bool,
+    pub pid: Option<u32>,
+    pub restart_count: u32,
+    pub last_exit_code: Option<i32>,
+}
+
+impl BackendState {
+    pub fn new(workspace: &str) -> Self {
+        Self {
+            workspace: workspace.to_string(),
+            child: None,
+            status: BackendStatus::Starting,
+            port: 0,
+            api_token: auth::generate_token(),
+            external_url: None,
+            generation: 0,
+            restart_count: 0,
+            last_exit_code: None,
+            recent_restarts: VecDeque::new(),
+            stderr_tail: VecDeque::with_capacity(STDERR_TAIL_LINES),
+            compat: None,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        match &self.external_url {
+            Some(url) => url.clone(),
+            None => format!("http://127.0.0.1:{}", self.port),
+        }
+    }
+
+    pub fn snapshot(&self) -> StatusSnapshot {
+        StatusSnapshot {
+            status: self.status,
+            url: self.base_url(),
+            external: self.external_url.is_some(),
+            pid: self.child.as_ref().map(|child| child.pid()),
+            restart_count: self.restart_count,
+            last_exit_code: self.last_exit_code,
+        }
+    }
+
+    fn push_stderr(&mut self, line: String) {
+        if self.stderr_tail.len() == STDERR_TAIL_LINES {
+            self.stderr_tail.pop_front();
+        }
+        self.stderr_tail.push_back(line);
+    }
+}
+
+/// Connect to the external backend from the launch options, or spawn the sidecar.
+pub fn start(app: &AppHandle) -> Result<(), StartupError> {
+    // Every API call goes through it, so there's no point starting without it
+    app.state::<Proxy>().client()?;
+    let options = app.state::<LaunchOptions>().inner().clone();
+    match options.backend_url {
+        Some(url) => {
+            tauri::async_runtime::spawn(health::connect_external(
+                app.clone(),
+                url,
+                options.backend_api_key,
+                options.sidecar_fallback,
+            ));
+            Ok(())
+        }
+        None => start_sidecar(app),
+    }
+}
+
+/// Check the data dir, then spawn the sidecar.
+pub fn start_sidecar(app: &AppHandle) -> Result<(), StartupError> {
+    startup::check_data_dir(app)?;
+    spawn(app)
+}
+
+/// Spawn the active workspace's sidecar, start watching its output and start
+/// polling for readiness.
+pub fn spawn(app: &AppHandle) -> Result<(), StartupError> {
+    let settings = app.state::<Mutex<BackendSettings>>().lock().unwrap().clone();
+    let data_dir = app.state::<DataDir>().path();
+    spawn_with(app, state(app), settings, data_dir)
+}
+
+/// `spawn` for workspace `id`, with its own settings and data folder even if
+/// another workspace has been opened since.
+pub fn spawn_for(app: &AppHandle, id: &str) -> Result<(), StartupError> {
+    let settings = settings::load_of(app, id);
+    let data_dir = datadir::path_of(app, id);
+    spawn_with(app, app.state::<Backends>().get(id), settings, data_dir)
+}
+
+fn spawn_with(
+    app: &AppHandle,
+    // Held on to, so this sidecar's events still reach its own workspace's
+    // state after a switch
+    state: Arc<Mutex<BackendState>>,
+    settings: BackendSettings,
+    data_dir: Option<PathBuf>,
+) -> Result<(), StartupError> {
+    // Keep the port across restarts so the UI's URL stays valid, unless
+    // something else has taken it in the meantime
+    let env = {
+        let mut state = state.lock().unwrap();
+        if state.port == 0 || !port::is_free(state.port) {
+            state.port =
+                port::pick_free().map_err(|e| StartupError::PortUnavailable(e.to_string()))?;
+        }
+        sidecar_env(&settings, data_dir.as_deref(), state.port, &state.api_token)
+    };
+
+    let mut command = Command::new_sidecar(SIDECAR)
+        .map_err(|e| StartupError::SidecarMissing(e.to_string()))?
+        .envs(env);
+    if let Some(dir) = data_dir {
+        // Anything the backend writes relative to its working directory
+        // belongs with the chain, not wherever the app was launched from
+        command = command.current_dir(dir);
+    }
+    let (rx, child) = command
+        .spawn()
+        .map_err(StartupError::from_spawn)?;
+    let pid = child.pid();
+
+    let generation = {
+        let mut state = state.lock().unwrap();
+        state.child = Some(child);
+        state.status = BackendStatus::Starting;
+        state.generation += 1;
+        state.generation
+    };
+    emit_status(app);
+
+    watch_output(app.clone(), state.clone(), rx, pid, generation);
+    tauri::async_runtime::spawn(health::wait_for_ready(app.clone(), state, generation));
+
+    Ok(())
+}
+
+/// Environment for the sidecar on top of the shell's own: the user's
+/// settings, then what the shell needs the backend to do regardless of them.
+fn sidecar_env(
+    settings: &BackendSettings,
+    data_dir: Option<&Path>,
+    port: u16,
+    api_token: &str,
+) -> HashMap<String, String> {
+    let mut env = settings.to_env();
+    env.insert("HOST".into(), "127.0.0.1".into());
+    env.insert("PORT".into(), port.to_string());
+    // Our token is the only key the backend accepts; NATLANGCHAIN_API_KEYS
+    // takes precedence over NATLANGCHAIN_API_KEY, so set both
+    env.insert("NATLANGCHAIN_REQUIRE_AUTH".into(), "true".into());
+    env.insert("NATLANGCHAIN_API_KEYS".into(), api_token.into());
+    env.insert("NATLANGCHAIN_API_KEY".into(), api_token.into());
+    // Only the api_request proxy talks to the backend, so no browser origin needs CORS
+    env.insert("CORS_ALLOWED_ORIGINS".into(), String::new());
+    // One JSON record per line, which the Debug window's level filter parses
+    env.insert("LOG_FORMAT".into(), "json".into());
+    if let Some(dir) = data_dir {
+        let chain_file = dir.join(datadir::CHAIN_FILE);
+        env.insert("CHAIN_DATA_FILE".into(), chain_file.display().to_string());
+    }
+    env
+}
+
+/// Forget a failed start so the next one begins from scratch. The sidecar
+/// must already be stopped.
+pub fn reset_for_retry(app: &AppHandle) {
+    {
+        let state = state(app);
+        let mut state = state.lock().unwrap();
+        state.status = BackendStatus::Starting;
+        state.api_token = auth::generate_token();
+        state.external_url = None;
+        state.recent_restarts.clear();
+        state.stderr_tail.clear();
+    }
+    emit_status(app);
+}
+
+pub fn emit_status(app: &AppHandle) {
+    let snapshot = state(app).lock().unwrap().snapshot();
+    let _ = app.emit_all(EVENT_STATUS, snapshot);
+}
+
+/// Forward sidecar output to the shell's console and `backend.log` until the
+/// process exits.
+fn watch_output(
+    app: AppHandle,
+    state: Arc<Mutex<BackendState>>,
+    mut rx: Receiver<CommandEvent>,
+    pid: u32,
+    generation: u64,
+) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(line) => {
+                    println!("[Backend] {}", line);
+                    logs::backend(&LogRecord::now(Stream::Stdout, pid, line.clone()));
+                    let parsed = logstream::push(&app, Stream::Stdout, pid, &line);
+                    auth::check_access_log(&app, &parsed.message);
+                }
+                CommandEvent::Stderr(line) => {
+                    eprintln!("[Backend Error] {}", line);
+                    logs::backend(&LogRecord::now(Stream::Stderr, pid, line.clone()));
+                    let parsed = logstream::push(&app, Stream::Stderr, pid, &line);
+                    auth::check_access_log(&app, &parsed.message);
+                    state.lock().unwrap().push_stderr(line);
+                }
+                CommandEvent::Terminated(payload) => {
+                    log_info!("[Backend] Process {} terminated with code: {:?}", pid, payload.code);
+                    supervisor::on_terminated(&app, &state, generation, payload.code);
+                    break;
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn backend_ready(app: AppHandle) -> bool {
+    state(&app).lock().unwrap().status == BackendStatus::Ready
+}
+
+#[tauri::command]
+pub fn get_backend_url(app: AppHandle) -> String {
+    state(&app).lock().unwrap().base_url()
+}
+
+#[tauri::command]
+pub fn backend_status(app: AppHandle) -> StatusSnapshot {
+    state(&app).lock().unwrap().snapshot()
+}
@@ -0,0 +1,184 @@
+//! Reading chain files the way `JSONFileStorage.load_chain` does.
+//!
+//! The backend writes `NatLangChain.to_dict()` as compact JSON, gzip
+//! compressed by default and then encrypted if `NATLANGCHAIN_ENCRYPTION_KEY`
+//! is set. Exports and `/chain` responses are plain JSON and may be just the
+//! list of blocks; all of those are accepted here.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::datadir::{DataDir, CHAIN_FILE};
+use crate::encryption::{self, EncryptionError};
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+/// `ENCRYPTION_PREFIX` in `src/encryption.py`.
+pub const ENCRYPTION_PREFIX: &str = "ENC:";
+
+#[derive(Debug)]
+pub enum ChainFileError {
+    Io(io::Error),
+    /// Written with `NATLANGCHAIN_ENCRYPTION_KEY` set, and no key was given.
+    Encrypted,
+    WrongKey,
+    Invalid(String),
+}
+
+impl fmt::Display for ChainFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainFileError::Io(e) => write!(f, "Could not read the chain file ({}).", e),
+            ChainFileError::Encrypted => {
+                f.write_str("The chain file is encrypted. Open it with its encryption key.")
+            }
+            ChainFileError::WrongKey => {
+                f.write_str("The encryption key does not match the chain file.")
+            }
+            ChainFileError::Invalid(reason) => {
+                write!(f, "The file is not a NatLangChain chain ({}).", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainFileError {}
+
+impl From<io::Error> for ChainFileError {
+    fn from(e: io::Error) -> Self {
+        ChainFileError::Io(e)
+    }
+}
+
+/// `path` if given, otherwise the current workspace's saved chain.
+pub fn locate(app: &AppHandle, path: Option<PathBuf>) -> Result<PathBuf, String> {
+    if let Some(path) = path {
+        return Ok(path);
+    }
+    let dir = app.state::<DataDir>().path().ok_or("No data folder on this platform")?;
+    let path = dir.join(CHAIN_FILE);
+    if !path.exists() {
+        return Err("No chain has been saved in this workspace yet.".into());
+    }
+    Ok(path)
+}
+
+/// Read, decrypt and decompress a chain file into its JSON document. `key`
+/// is only needed for encrypted files.
+pub fn read(path: &Path, key: Option<&str>) -> Result<Value, ChainFileError> {
+    let raw = fs::read(path)?;
+    parse(&raw, key)
+}
+
+fn parse(raw: &[u8], key: Option<&str>) -> Result<Value, ChainFileError> {
+    // Encryption wraps compression, as in `JSONFileStorage.save_chain`
+    let raw = if raw.starts_with(ENCRYPTION_PREFIX.as_bytes()) {
+        decrypt(raw, key.ok_or(ChainFileError::Encrypted)?)?
+    } else {
+        raw.to_vec()
+    };
+    let json = decompress(&raw)?;
+    serde_json::from_slice(&json).map_err(|e| ChainFileError::Invalid(e.to_string()))
+}
+
+fn decrypt(raw: &[u8], key: &str) -> Result<Vec<u8>, ChainFileError> {
+    let text = std::str::from_utf8(raw)
+        .map_err(|_| ChainFileError::Invalid("encrypted data is not text".into()))?;
+    encryption::decrypt(text, key).map_err(|e| match e {
+        EncryptionError::WrongKey => ChainFileError::WrongKey,
+        EncryptionError::Format(reason) => ChainFileError::Invalid(reason.into()),
+    })
+}
+
+/// Gunzip `raw` if it is gzip data, otherwise return it as it is.
+fn decompress(raw: &[u8]) -> Result<Vec<u8>, ChainFileError> {
+    if !raw.starts_with(GZIP_MAGIC) {
+        return Ok(raw.to_vec());
+    }
+    let mut json = Vec::new();
+    GzDecoder::new(raw)
+        .read_to_end(&mut json)
+        .map_err(|e| ChainFileError::Invalid(format!("invalid gzip data: {}", e)))?;
+    Ok(json)
+}
+
+/// The blocks of a `to_dict()` export, a `/chain` response, or a bare list.
+pub fn blocks(data: Value) -> Result<Vec<Value>, ChainFileError> {
+    match data {
+        Value::Array(blocks) => Ok(blocks),
+        Value::Object(mut map) => match map.remove("chain") {
+            Some(Value::Array(blocks)) => Ok(blocks),
+            _ => Err(ChainFileError::Invalid("no \"chain\" list".into())),
+        },
+        _ => Err(ChainFileError::Invalid("expected a JSON object or list".into())),
+    }
+}
+
+/// The JSON document in the chain file at `path`, or in the current
+/// workspace's saved chain, decrypted with `key` (default: the workspace's
+/// encryption key) if needed. Sensitive metadata fields stay encrypted; see
+/// `encryption::decrypt_metadata`.
+#[tauri::command]
+pub async fn open_chain_file(
+    app: AppHandle,
+    path: Option<PathBuf>,
+    key: Option<String>,
+) -> Result<Value, String> {
+    let path = locate(&app, path)?;
+    let key = encryption::key_or_default(&app, key);
+    tauri::async_runtime::spawn_blocking(move || {
+        read(&path, key.as_deref()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    // Saved by `JSONFileStorage` with compression and encryption on; see
+    // tests/fixtures/generate.py
+    const ENCRYPTED: &[u8] = include_bytes!("../tests/fixtures/chain_encrypted.dat");
+    const PLAIN: &str = include_str!("../tests/fixtures/chain.json");
+    const KEY: &str = "fixture encryption key";
+
+    fn expected() -> Vec<Value> {
+        blocks(serde_json::from_str(PLAIN).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn reads_encrypted_gzipped_chains() {
+        let data = parse(ENCRYPTED, Some(KEY)).unwrap();
+        assert_eq!(blocks(data).unwrap(), expected());
+    }
+
+    #[test]
+    fn needs_the_right_key() {
+        assert!(matches!(parse(ENCRYPTED, None), Err(ChainFileError::Encrypted)));
+        assert!(matches!(
+            parse(ENCRYPTED, Some("not the fixture key")),
+            Err(ChainFileError::WrongKey)
+        ));
+    }
+
+    #[test]
+    fn reads_plain_and_gzipped_chains() {
+        assert_eq!(blocks(parse(PLAIN.as_bytes(), None).unwrap()).unwrap(), expected());
+
+        let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+        gzipped.write_all(PLAIN.as_bytes()).unwrap();
+        let gzipped = gzipped.finish().unwrap();
+        assert_eq!(blocks(parse(&gzipped, None).unwrap()).unwrap(), expected());
+    }
+}
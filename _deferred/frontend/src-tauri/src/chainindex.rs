@@ -0,0 +1,749 @@
+//! Local index of the backend's chain.
+//!
+//! Rather than fetch the whole `/chain` document for every view, the explorer
+//! reads `chain_index.sqlite3` in the workspace's app data folder, which has a
+//! row per block and per entry. The shell keeps it current by polling
+//! `GET /block/latest` and fetching only the blocks it doesn't have yet from
+//! `/block/<i>`, each of which must link to the hash of the block before it.
+//! If the backend's chain no longer holds the indexed tip (it was reset,
+//! replaced or shortened), the index is rebuilt from scratch;
+//! `resync_chain_index` does the same on request.
+//!
+//! `list_indexed_blocks` pages through the blocks and `query_indexed_entries`
+//! filters entries by author, intent, derivative type and date.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{SecondsFormat, Utc};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::backend::{self, Backends, BackendStatus};
+use crate::proxy::Proxy;
+use crate::{auth, workspace};
+
+const INDEX_FILE: &str = "chain_index.sqlite3";
+
+pub const EVENT_SYNCED: &str = "index://synced";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+/// Blocks fetched before they're written, in one transaction.
+const FETCH_BATCH: u64 = 50;
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Bump when the tables change; an index with another version is rebuilt.
+const SCHEMA_VERSION: i64 = 1;
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS blocks (
+        block_index INTEGER PRIMARY KEY,
+        hash TEXT NOT NULL,
+        previous_hash TEXT NOT NULL,
+        timestamp REAL,
+        entry_count INTEGER NOT NULL,
+        -- The block as /block/<i> returned it
+        block TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS entries (
+        block_index INTEGER NOT NULL,
+        entry_index INTEGER NOT NULL,
+        author TEXT NOT NULL,
+        intent TEXT NOT NULL,
+        -- Lowercased by the shell; SQLite's lower() only knows ASCII
+        intent_lower TEXT NOT NULL,
+        derivative_type TEXT,
+        timestamp TEXT,
+        entry TEXT NOT NULL,
+        PRIMARY KEY (block_index, entry_index)
+    );
+    CREATE INDEX IF NOT EXISTS entries_author ON entries (author);
+    CREATE INDEX IF NOT EXISTS entries_derivative_type ON entries (derivative_type);
+    CREATE INDEX IF NOT EXISTS entries_timestamp ON entries (timestamp);
+";
+
+/// The open index, and how the last sync went.
+#[derive(Default)]
+pub struct ChainIndex {
+    db: Mutex<Option<Db>>,
+    syncing: AtomicBool,
+    last_sync: Mutex<LastSync>,
+}
+
+impl ChainIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+struct Db {
+    path: PathBuf,
+    conn: Connection,
+}
+
+#[derive(Default)]
+struct LastSync {
+    at: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct IndexStatus {
+    pub blocks: u64,
+    pub entries: u64,
+    /// Hash of the last indexed block.
+    pub tip: Option<String>,
+    /// When the index last caught up with the backend (RFC 3339).
+    pub synced_at: Option<String>,
+    pub syncing: bool,
+    /// Why the last sync failed, if it did.
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct Page {
+    pub total: u64,
+    pub offset: u32,
+    pub items: Vec<Value>,
+}
+
+/// Every field is optional; the ones given must all match.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct EntryFilter {
+    pub author: Option<String>,
+    /// Contained in the intent, ignoring case, as `get_entries_by_intent` matches.
+    pub intent: Option<String>,
+    pub derivative_type: Option<String>,
+    /// Entry timestamps from `since` up to but not including `until`, both
+    /// ISO 8601 such as `2026-10-15` or `2026-10-15T12:00`.
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+enum Synced {
+    UpToDate,
+    Added(u64),
+    /// The backend's chain doesn't continue the indexed one.
+    Diverged,
+}
+
+fn open(path: &Path) -> rusqlite::Result<Connection> {
+    prepare(Connection::open(path)?)
+}
+
+/// Create the tables, or rebuild them if they are from another version.
+fn prepare(conn: Connection) -> rusqlite::Result<Connection> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version != SCHEMA_VERSION {
+        // Only a copy of the backend's chain, so start over
+        conn.execute_batch("DROP TABLE IF EXISTS entries; DROP TABLE IF EXISTS blocks;")?;
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    Ok(conn)
+}
+
+/// Run `query` on workspace `id`'s index, opening it first if needed.
+fn with_db<T>(
+    app: &AppHandle,
+    id: &str,
+    query: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    let path = workspace::data_root_of(app, id)
+        .ok_or("No data folder on this platform")?
+        .join(INDEX_FILE);
+    let index = app.state::<ChainIndex>();
+    let mut db = index.db.lock().unwrap();
+    let current = match db.take() {
+        Some(current) if current.path == path => current,
+        // First use, or another workspace was opened
+        _ => {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+            }
+            let conn =
+                open(&path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+            Db { path, conn }
+        }
+    };
+    let current = db.insert(current);
+    query(&mut current.conn).map_err(|e| format!("Chain index: {}", e))
+}
+
+/// `with_db` on a blocking thread.
+async fn blocking<T: Send + 'static>(
+    app: AppHandle,
+    id: String,
+    query: impl FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(move || with_db(&app, &id, query))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// The last indexed block's index and hash.
+fn tip(conn: &mut Connection) -> rusqlite::Result<Option<(u64, String)>> {
+    conn.query_row(
+        "SELECT block_index, hash FROM blocks ORDER BY block_index DESC LIMIT 1",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+fn clear(conn: &mut Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("DELETE FROM entries; DELETE FROM blocks;")
+}
+
+fn insert(conn: &mut Connection, blocks: &[Value]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut block_row = tx.prepare(
+            "INSERT OR REPLACE INTO blocks
+                 (block_index, hash, previous_hash, timestamp, entry_count, block)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut entry_row = tx.prepare(
+            "INSERT OR REPLACE INTO entries
+                 (block_index, entry_index, author, intent, intent_lower,
+                  derivative_type, timestamp, entry)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for block in blocks {
+            let index = block["index"].as_u64();
+            let entries = block["entries"].as_array().map_or(&[][..], Vec::as_slice);
+            block_row.execute(params![
+                index,
+                block["hash"].as_str(),
+                block["previous_hash"].as_str(),
+                block["timestamp"].as_f64(),
+                entries.len() as i64,
+                block.to_string(),
+            ])?;
+            for (position, entry) in entries.iter().enumerate() {
+                let intent = entry["intent"].as_str().unwrap_or_default();
+                entry_row.execute(params![
+                    index,
+                    position as i64,
+                    entry["author"].as_str().unwrap_or_default(),
+                    intent,
+                    intent.to_lowercase(),
+                    entry["derivative_type"].as_str(),
+                    entry["timestamp"].as_str(),
+                    entry.to_string(),
+                ])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+/// The `WHERE` clause for `filter`, and the values of its parameters.
+fn where_clause(filter: &EntryFilter) -> (String, Vec<SqlValue>) {
+    let mut conditions = Vec::new();
+    let mut values: Vec<SqlValue> = Vec::new();
+    let given = |value: &Option<String>| value.clone().filter(|value| !value.trim().is_empty());
+    if let Some(author) = given(&filter.author) {
+        conditions.push("entries.author = ?");
+        values.push(author.into());
+    }
+    if let Some(intent) = given(&filter.intent) {
+        conditions.push("instr(entries.intent_lower, ?) > 0");
+        values.push(intent.to_lowercase().into());
+    }
+    if let Some(derivative_type) = given(&filter.derivative_type) {
+        conditions.push("entries.derivative_type = ?");
+        values.push(derivative_type.into());
+    }
+    if let Some(since) = given(&filter.since) {
+        conditions.push("entries.timestamp >= ?");
+        values.push(since.into());
+    }
+    if let Some(until) = given(&filter.until) {
+        conditions.push("entries.timestamp < ?");
+        values.push(until.into());
+    }
+    let clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    (clause, values)
+}
+
+fn entries_page(
+    conn: &mut Connection,
+    filter: &EntryFilter,
+    offset: u32,
+    limit: u32,
+) -> rusqlite::Result<Page> {
+    let (clause, values) = where_clause(filter);
+    let total = conn.query_row(
+        &format!("SELECT COUNT(*) FROM entries {}", clause),
+        params_from_iter(values.iter()),
+        |row| row.get(0),
+    )?;
+    let mut statement = conn.prepare(&format!(
+        "SELECT entries.block_index, entries.entry_index, blocks.hash, entries.entry
+         FROM entries JOIN blocks ON blocks.block_index = entries.block_index
+         {}
+         ORDER BY entries.block_index DESC, entries.entry_index DESC
+         LIMIT {} OFFSET {}",
+        clause, limit, offset
+    ))?;
+    let items = statement
+        .query_map(params_from_iter(values.iter()), |row| {
+            Ok(json!({
+                "block_index": row.get::<_, u64>(0)?,
+                "entry_index": row.get::<_, u64>(1)?,
+                "block_hash": row.get::<_, String>(2)?,
+                "entry": parse(&row.get::<_, String>(3)?),
+            }))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(Page {
+        total,
+        offset,
+        items,
+    })
+}
+
+async fn status(app: &AppHandle) -> Result<IndexStatus, String> {
+    let (blocks, entries, tip) = blocking(app.clone(), workspace::active_id(app), |conn| {
+        let blocks = conn.query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0))?;
+        let entries = conn.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))?;
+        Ok((blocks, entries, tip(conn)?.map(|(_, hash)| hash)))
+    })
+    .await?;
+    let index = app.state::<ChainIndex>();
+    let last_sync = index.last_sync.lock().unwrap();
+    Ok(IndexStatus {
+        blocks,
+        entries,
+        tip,
+        synced_at: last_sync.at.clone(),
+        syncing: index.syncing.load(Ordering::SeqCst),
+        error: last_sync.error.clone(),
+    })
+}
+
+fn backend_ready(app: &AppHandle) -> bool {
+    backend::state(app).lock().unwrap().status == BackendStatus::Ready
+}
+
+/// `GET path` from workspace `id`'s backend, the way `api_request` would.
+async fn fetch(app: &AppHandle, id: &str, path: &str) -> Result<Value, String> {
+    let (base_url, api_token) = {
+        let state = app.state::<Backends>().get(id);
+        let state = state.lock().unwrap();
+        (state.base_url(), state.api_token.clone())
+    };
+    let client = app.state::<Proxy>().client().map_err(|e| e.to_string())?;
+    let mut request = client.get(format!("{}{}", base_url, path));
+    if !api_token.is_empty() {
+        request = request.header(auth::HEADER, &api_token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|_| "The backend is unavailable.".to_string())?;
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("GET {}: {}", path, e))?;
+    if !status.is_success() {
+        let error = body["error"].as_str().unwrap_or("no details");
+        return Err(format!(
+            "GET {} answered {}: {}",
+            path,
+            status.as_u16(),
+            error
+        ));
+    }
+    Ok(body)
+}
+
+async fn sync_once(app: &AppHandle, id: &str) -> Result<Synced, String> {
+    let latest = fetch(app, id, "/block/latest").await?;
+    let length = latest["chain_length"]
+        .as_u64()
+        .ok_or("/block/latest has no chain_length")?;
+    let latest = &latest;
+    let fetch_block = |index: u64| async move {
+        if index + 1 == length {
+            Ok(latest["block"].clone())
+        } else {
+            fetch(app, id, &format!("/block/{}", index)).await
+        }
+    };
+
+    let (start, mut previous) = match blocking(app.clone(), id.to_string(), tip).await? {
+        None => (0, None),
+        Some((index, _)) if index >= length => return Ok(Synced::Diverged),
+        Some((index, hash)) => {
+            if fetch_block(index).await?["hash"].as_str() != Some(hash.as_str()) {
+                return Ok(Synced::Diverged);
+            }
+            (index + 1, Some(hash))
+        }
+    };
+
+    let mut next = start;
+    while next < length {
+        let end = (next + FETCH_BATCH).min(length);
+        let mut batch = Vec::new();
+        for index in next..end {
+            let block = fetch_block(index).await?;
+            if block["index"].as_u64() != Some(index) {
+                return Err(format!(
+                    "/block/{} returned block {}",
+                    index, block["index"]
+                ));
+            }
+            if let Some(hash) = &previous {
+                if block["previous_hash"].as_str() != Some(hash.as_str()) {
+                    return Ok(Synced::Diverged);
+                }
+            }
+            previous = block["hash"].as_str().map(str::to_string);
+            batch.push(block);
+        }
+        blocking(app.clone(), id.to_string(), move |conn| insert(conn, &batch)).await?;
+        next = end;
+        // A first sync of a long chain takes a while; show how far it got
+        if next < length {
+            emit(app).await;
+        }
+    }
+    Ok(match length - start {
+        0 => Synced::UpToDate,
+        added => Synced::Added(added),
+    })
+}
+
+/// Catch up with the backend, rebuilding the index if the chains diverged.
+/// Resolves to whether the index changed.
+async fn run_sync(app: &AppHandle, id: &str, mut from_scratch: bool) -> Result<bool, String> {
+    // Twice at most: a chain that diverges again during a rebuild is left
+    // for the next round
+    for _ in 0..2 {
+        if from_scratch {
+            log_info!("[Shell] Rebuilding the chain index");
+            blocking(app.clone(), id.to_string(), clear).await?;
+        }
+        match sync_once(app, id).await? {
+            Synced::UpToDate => return Ok(from_scratch),
+            Synced::Added(blocks) => {
+                log_info!("[Shell] Indexed {} new blocks", blocks);
+                return Ok(true);
+            }
+            Synced::Diverged => {
+                log_info!("[Shell] The backend's chain no longer matches the index");
+                from_scratch = true;
+            }
+        }
+    }
+    Err("The backend's chain kept changing while it was being indexed.".into())
+}
+
+async fn sync(app: &AppHandle, from_scratch: bool) -> Result<IndexStatus, String> {
+    // One at a time; a sync asked for during another runs after it
+    while app
+        .state::<ChainIndex>()
+        .syncing
+        .swap(true, Ordering::SeqCst)
+    {
+        tokio::time::sleep(WAIT_INTERVAL).await;
+    }
+    // The workspace open now; one opened mid-sync is synced by the next round
+    // rather than getting the rest of this one's blocks
+    let id = workspace::active_id(app);
+    let result = run_sync(app, &id, from_scratch).await;
+    let index = app.state::<ChainIndex>();
+    index.syncing.store(false, Ordering::SeqCst);
+
+    let changed = {
+        let mut last_sync = index.last_sync.lock().unwrap();
+        match &result {
+            Ok(changed) => {
+                last_sync.at = Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+                last_sync.error.take().is_some() || *changed
+            }
+            Err(e) => {
+                let repeated = last_sync.error.as_ref() == Some(e);
+                if !repeated {
+                    log_error!("[Shell] Chain index sync failed: {}", e);
+                }
+                last_sync.error = Some(e.clone());
+                !repeated
+            }
+        }
+    };
+    if changed {
+        emit(app).await;
+    }
+    result?;
+    status(app).await
+}
+
+async fn emit(app: &AppHandle) {
+    match status(app).await {
+        Ok(status) => {
+            let _ = app.emit_all(EVENT_SYNCED, status);
+        }
+        Err(e) => log_error!("[Shell] {}", e),
+    }
+}
+
+/// Keep the index in step with the backend whenever it is ready.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if backend_ready(&app) {
+                // Failures are recorded in the status
+                let _ = sync(&app, false).await;
+            }
+        }
+    });
+}
+
+fn page_size(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+fn parse(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or(Value::Null)
+}
+
+#[tauri::command]
+pub async fn chain_index_status(app: AppHandle) -> Result<IndexStatus, String> {
+    status(&app).await
+}
+
+/// Catch up with the backend now. Resolves to the index's status.
+#[tauri::command]
+pub async fn sync_chain_index(app: AppHandle) -> Result<IndexStatus, String> {
+    if !backend_ready(&app) {
+        return Err("The backend is not running.".into());
+    }
+    sync(&app, false).await
+}
+
+/// Throw the index away and fetch every block again.
+#[tauri::command]
+pub async fn resync_chain_index(app: AppHandle) -> Result<IndexStatus, String> {
+    if !backend_ready(&app) {
+        return Err("The backend is not running.".into());
+    }
+    sync(&app, true).await
+}
+
+/// A page of indexed blocks, as `/block/<i>` returns them, newest first
+/// unless `newest_first` is false.
+#[tauri::command]
+pub async fn list_indexed_blocks(
+    app: AppHandle,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    newest_first: Option<bool>,
+) -> Result<Page, String> {
+    let offset = offset.unwrap_or(0);
+    let limit = page_size(limit);
+    let order = if newest_first.unwrap_or(true) {
+        "DESC"
+    } else {
+        "ASC"
+    };
+    blocking(app.clone(), workspace::active_id(&app), move |conn| {
+        let total = conn.query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0))?;
+        let mut statement = conn.prepare(&format!(
+            "SELECT block FROM blocks ORDER BY block_index {} LIMIT ?1 OFFSET ?2",
+            order
+        ))?;
+        let items = statement
+            .query_map(params![limit, offset], |row| row.get::<_, String>(0))?
+            .map(|text| text.map(|text| parse(&text)))
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(Page {
+            total,
+            offset,
+            items,
+        })
+    })
+    .await
+}
+
+/// A page of the indexed entries that match `filter`, newest first, each as
+/// `{ block_index, entry_index, block_hash, entry }`.
+#[tauri::command]
+pub async fn query_indexed_entries(
+    app: AppHandle,
+    filter: Option<EntryFilter>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Result<Page, String> {
+    let filter = filter.unwrap_or_default();
+    let offset = offset.unwrap_or(0);
+    let limit = page_size(limit);
+
+    blocking(app.clone(), workspace::active_id(&app), move |conn| {
+        entries_page(conn, &filter, offset, limit)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(author: &str, intent: &str, timestamp: &str) -> Value {
+        json!({ "author": author, "intent": intent, "content": "...", "timestamp": timestamp })
+    }
+
+    fn block(index: u64, entries: Vec<Value>) -> Value {
+        json!({
+            "index": index,
+            "hash": format!("hash{}", index),
+            "previous_hash": if index == 0 { "0".to_string() } else { format!("hash{}", index - 1) },
+            "timestamp": 1760000000.0 + index as f64,
+            "entries": entries,
+        })
+    }
+
+    fn indexed() -> Connection {
+        let mut conn = prepare(Connection::open_in_memory().unwrap()).unwrap();
+        let mut derivative = entry(
+            "bob",
+            "Amend the delivery terms",
+            "2026-10-15T09:30:00.000000",
+        );
+        derivative["derivative_type"] = json!("amendment");
+        let blocks = [
+            block(
+                0,
+                vec![entry("system", "Genesis", "2026-10-14T23:59:59.999999")],
+            ),
+            block(
+                1,
+                vec![
+                    entry(
+                        "alice",
+                        "Über delivery of goods",
+                        "2026-10-15T08:00:00.000000",
+                    ),
+                    derivative,
+                ],
+            ),
+            block(
+                2,
+                vec![entry("alice", "Payment", "2026-10-16T00:00:00.000000")],
+            ),
+        ];
+        insert(&mut conn, &blocks).unwrap();
+        conn
+    }
+
+    fn filter(fields: Value) -> EntryFilter {
+        serde_json::from_value(fields).unwrap()
+    }
+
+    /// `(block_index, entry_index)` of each entry on the page
+    fn positions(page: &Page) -> Vec<(u64, u64)> {
+        page.items
+            .iter()
+            .map(|item| {
+                (
+                    item["block_index"].as_u64().unwrap(),
+                    item["entry_index"].as_u64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn query(fields: Value) -> Vec<(u64, u64)> {
+        positions(&entries_page(&mut indexed(), &filter(fields), 0, 50).unwrap())
+    }
+
+    #[test]
+    fn inserts_blocks_and_finds_the_tip() {
+        let mut conn = indexed();
+        assert_eq!(tip(&mut conn).unwrap(), Some((2, "hash2".to_string())));
+        // Fetching a block again replaces it
+        insert(&mut conn, &[block(2, vec![])]).unwrap();
+        let count: u64 = conn
+            .query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+
+        clear(&mut conn).unwrap();
+        assert_eq!(tip(&mut conn).unwrap(), None);
+    }
+
+    #[test]
+    fn rebuilds_an_index_from_another_schema_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE blocks (block_index INTEGER PRIMARY KEY, data TEXT);
+             INSERT INTO blocks VALUES (0, 'old');",
+        )
+        .unwrap();
+        let mut conn = prepare(conn).unwrap();
+        let version: i64 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        assert_eq!(tip(&mut conn).unwrap(), None);
+        insert(&mut conn, &[block(0, vec![])]).unwrap();
+
+        // The current version is left alone
+        let mut conn = prepare(conn).unwrap();
+        assert_eq!(tip(&mut conn).unwrap(), Some((0, "hash0".to_string())));
+    }
+
+    #[test]
+    fn ignores_blank_filters() {
+        let (clause, values) = where_clause(&filter(json!({ "author": " ", "intent": "" })));
+        assert_eq!(clause, "");
+        assert!(values.is_empty());
+        assert_eq!(query(json!({})), [(2, 0), (1, 1), (1, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn filters_by_author_intent_and_type() {
+        assert_eq!(query(json!({ "author": "alice" })), [(2, 0), (1, 0)]);
+        // A case-insensitive substring, beyond ASCII too
+        assert_eq!(query(json!({ "intent": "DELIVERY" })), [(1, 1), (1, 0)]);
+        assert_eq!(query(json!({ "intent": "über" })), [(1, 0)]);
+        assert_eq!(query(json!({ "derivative_type": "amendment" })), [(1, 1)]);
+        assert_eq!(query(json!({ "author": "alice", "intent": "terms" })), []);
+    }
+
+    #[test]
+    fn filters_by_date_range() {
+        let day = json!({ "since": "2026-10-15", "until": "2026-10-16" });
+        assert_eq!(query(day), [(1, 1), (1, 0)]);
+        assert_eq!(query(json!({ "since": "2026-10-15T09" })), [(2, 0), (1, 1)]);
+        assert_eq!(query(json!({ "until": "2026-10-15" })), [(0, 0)]);
+    }
+
+    #[test]
+    fn pages_newest_first() {
+        let page = entries_page(&mut indexed(), &EntryFilter::default(), 1, 2).unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(page.offset, 1);
+        assert_eq!(positions(&page), [(1, 1), (1, 0)]);
+        assert_eq!(page.items[0]["block_hash"], "hash1");
+        assert_eq!(page.items[0]["entry"]["author"], "bob");
+    }
+}
@@ -0,0 +1,252 @@
+//! Version and capability handshake with the backend.
+//!
+//! Once the backend is ready the shell asks `/version` what it is and checks
+//! the answer against `COMPATIBILITY`, compiled in below. An API version this
+//! shell can't drive refuses the backend outright; a missing optional
+//! capability only switches off the UI features that need it.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use tauri::{AppHandle, Manager};
+
+use crate::auth;
+use crate::backend;
+use crate::startup::StartupError;
+
+pub const EVENT_COMPAT: &str = "backend://compat";
+
+const SHELL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Backend API versions (`API_VERSION` in `src/api/monitoring.py`) each shell
+/// release can drive, matched by version prefix. Add a row per release.
+const COMPATIBILITY: &[(&str, RangeInclusive<u32>)] = &[("0.1.", 1..=1)];
+
+/// UI features, the backend capability each one needs, and what to tell the
+/// user when it's missing.
+const FEATURES: &[(&str, &str, &str)] = &[
+    (
+        "contracts",
+        "contracts",
+        "Contracts need the backend's contract parser; is ANTHROPIC_API_KEY set?",
+    ),
+    (
+        "semantic_search",
+        "semantic_search",
+        "Semantic search is not available on this backend.",
+    ),
+    (
+        "llm_validation",
+        "llm_validation",
+        "Entries are not validated by an LLM on this backend.",
+    ),
+];
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CompatReport {
+    pub shell_version: String,
+    /// `None` if the backend predates the handshake.
+    pub backend_version: Option<String>,
+    pub api_version: Option<u32>,
+    /// UI features to switch off; names match the views where there is one.
+    pub disabled_features: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct VersionInfo {
+    version: String,
+    api_version: u32,
+    #[serde(default)]
+    capabilities: HashMap<String, bool>,
+}
+
+/// Ask the backend at `base_url` what it is and decide whether we can use it.
+pub async fn handshake(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+) -> Result<CompatReport, StartupError> {
+    let fetched = fetch_version(client, base_url, api_key)
+        .await
+        .map_err(|e| e.to_string());
+    assess(fetched)
+}
+
+/// Decide from the backend's `/version` answer (`None` if it has no such
+/// endpoint) whether we can use it.
+fn assess(fetched: Result<Option<VersionInfo>, String>) -> Result<CompatReport, StartupError> {
+    let mut report = CompatReport {
+        shell_version: SHELL_VERSION.to_string(),
+        backend_version: None,
+        api_version: None,
+        disabled_features: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let info = match fetched {
+        Ok(Some(info)) => info,
+        Ok(None) => {
+            report.warnings.push(
+                "The backend does not report its version; it may be too old for this app.".into(),
+            );
+            return Ok(report);
+        }
+        Err(e) => {
+            // The backend is up, so don't hold the app hostage over this
+            report.warnings.push(format!("Could not check the backend version: {}", e));
+            return Ok(report);
+        }
+    };
+
+    report.backend_version = Some(info.version.clone());
+    report.api_version = Some(info.api_version);
+
+    match supported_api_versions() {
+        Some(range) if !range.contains(&info.api_version) => {
+            return Err(StartupError::Incompatible(format!(
+                "backend {} speaks API version {}, this app needs {}",
+                info.version,
+                info.api_version,
+                describe(range)
+            )));
+        }
+        Some(_) => {}
+        None => report.warnings.push(format!(
+            "No compatibility data for app version {}; assuming backend {} works.",
+            SHELL_VERSION, info.version
+        )),
+    }
+
+    if major_minor(&info.version) != major_minor(SHELL_VERSION) {
+        report.warnings.push(format!(
+            "Backend version {} differs from app version {}.",
+            info.version, SHELL_VERSION
+        ));
+    }
+
+    for (feature, capability, warning) in FEATURES {
+        if !info.capabilities.get(*capability).copied().unwrap_or(false) {
+            report.disabled_features.push(feature.to_string());
+            report.warnings.push(warning.to_string());
+        }
+    }
+
+    Ok(report)
+}
+
+/// `None` if the backend has no `/version` endpoint.
+async fn fetch_version(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+) -> reqwest::Result<Option<VersionInfo>> {
+    let mut request = client.get(format!("{}/version", base_url));
+    if !api_key.is_empty() {
+        request = request.header(auth::HEADER, api_key);
+    }
+    let response = request.send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(response.error_for_status()?.json().await?))
+}
+
+/// Remember the report for `backend_compat` and tell the UI about it.
+pub fn apply(app: &AppHandle, report: CompatReport) {
+    for warning in &report.warnings {
+        log_error!("[Shell] Compatibility: {}", warning);
+    }
+    backend::state(app).lock().unwrap().compat = Some(report.clone());
+    let _ = app.emit_all(EVENT_COMPAT, report);
+}
+
+fn supported_api_versions() -> Option<&'static RangeInclusive<u32>> {
+    COMPATIBILITY
+        .iter()
+        .find(|(prefix, _)| SHELL_VERSION.starts_with(prefix))
+        .map(|(_, range)| range)
+}
+
+fn describe(range: &RangeInclusive<u32>) -> String {
+    if range.start() == range.end() {
+        range.start().to_string()
+    } else {
+        format!("{} to {}", range.start(), range.end())
+    }
+}
+
+fn major_minor(version: &str) -> Vec<&str> {
+    version.split(['.', '-']).take(2).collect()
+}
+
+/// The last handshake's result; `None` until the backend has been ready once.
+#[tauri::command]
+pub fn backend_compat(app: AppHandle) -> Option<CompatReport> {
+    backend::state(&app).lock().unwrap().compat.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(api_version: u32) -> VersionInfo {
+        VersionInfo {
+            version: SHELL_VERSION.into(),
+            api_version,
+            capabilities: FEATURES
+                .iter()
+                .map(|(_, capability, _)| (capability.to_string(), true))
+                .collect(),
+        }
+    }
+
+    fn supported() -> &'static RangeInclusive<u32> {
+        supported_api_versions().expect("a COMPATIBILITY row for this version")
+    }
+
+    #[test]
+    fn accepts_a_supported_api_version() {
+        let report = assess(Ok(Some(info(*supported().end())))).unwrap();
+        assert_eq!(report.api_version, Some(*supported().end()));
+        assert_eq!(report.backend_version.as_deref(), Some(SHELL_VERSION));
+        assert!(report.disabled_features.is_empty());
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn refuses_older_and_newer_api_versions() {
+        for api_version in [supported().start() - 1, supported().end() + 1] {
+            match assess(Ok(Some(info(api_version)))) {
+                Err(StartupError::Incompatible(message)) => {
+                    assert!(message.contains(&format!("API version {}", api_version)))
+                }
+                other => panic!("API version {}: {:?}", api_version, other),
+            }
+        }
+    }
+
+    #[test]
+    fn warns_about_a_backend_without_version() {
+        let report = assess(Ok(None)).unwrap();
+        assert_eq!(report.api_version, None);
+        assert_eq!(report.backend_version, None);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.disabled_features.is_empty());
+
+        let report = assess(Err("connection reset".into())).unwrap();
+        assert!(report.warnings[0].contains("connection reset"));
+    }
+
+    #[test]
+    fn switches_off_features_without_their_capability() {
+        let mut info = info(*supported().end());
+        info.capabilities.remove("semantic_search");
+        info.capabilities.insert("contracts".into(), false);
+        info.version = "9.9.0".into();
+        let report = assess(Ok(Some(info))).unwrap();
+        assert_eq!(report.disabled_features, ["contracts", "semantic_search"]);
+        // Plus one for the differing release
+        assert_eq!(report.warnings.len(), 3);
+    }
+}
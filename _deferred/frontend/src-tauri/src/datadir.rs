@@ -0,0 +1,398 @@
+//! Where the sidecar keeps the chain.
+//!
+//! `CHAIN_DATA_FILE` defaults to a path relative to the backend's working
+//! directory, which for a sidecar is wherever the app was launched from. The
+//! shell instead points it at `chain_data.json` in a data folder it owns:
+//! `data/` in the workspace's app data folder, or a folder the user chose,
+//! remembered in `data_dir.json` next to the workspace's settings. The
+//! sidecar also runs with that folder as its working directory, so nothing
+//! else lands next to the user's launch directory either.
+//!
+//! Relocating stops the sidecar, copies every file in the folder and its
+//! subfolders (the chain, its `.backup` copies, ...) to the new one, and only
+//! deletes the originals once all copies are in place.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+use tauri::{AppHandle, Manager};
+
+use crate::backend;
+use crate::{health, logs, shutdown, workspace};
+
+/// Same name as the backend's own default, so a folder moved between the
+/// desktop app and a manual install keeps working.
+pub const CHAIN_FILE: &str = "chain_data.json";
+
+const DEFAULT_DIR_NAME: &str = "data";
+const LOCATION_FILE: &str = "data_dir.json";
+
+/// Left behind by `startup::check_data_dir`; never worth moving.
+const IGNORED_FILES: &[&str] = &[".write-test"];
+
+/// The data folder in use; `None` if the platform has no app data dir, in
+/// which case the backend falls back to its own default.
+pub struct DataDir(Mutex<Option<PathBuf>>);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Location {
+    path: PathBuf,
+}
+
+#[derive(serde::Serialize)]
+pub struct DataDirInfo {
+    pub path: Option<PathBuf>,
+    pub default_path: Option<PathBuf>,
+    pub is_default: bool,
+    /// Whether a chain has been saved there yet.
+    pub chain_exists: bool,
+    /// Total size of the files in the folder, in bytes.
+    pub size: u64,
+}
+
+impl DataDir {
+    pub fn load(app: &AppHandle) -> Self {
+        DataDir(Mutex::new(resolve(app)))
+    }
+
+    /// Look the folder up again, after the active workspace changed.
+    pub fn reload(&self, app: &AppHandle) {
+        *self.0.lock().unwrap() = resolve(app);
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// The active workspace's chosen folder if there is one, otherwise the default.
+fn resolve(app: &AppHandle) -> Option<PathBuf> {
+    path_of(app, &workspace::active_id(app))
+}
+
+/// The data folder of workspace `id`, whether or not it is active.
+pub fn path_of(app: &AppHandle, id: &str) -> Option<PathBuf> {
+    let location = workspace::config_dir_of(app, id).map(|dir| dir.join(LOCATION_FILE));
+    let chosen = location.and_then(|path| match fs::read(&path) {
+        Ok(bytes) => match serde_json::from_slice::<Location>(&bytes) {
+            Ok(location) => Some(location.path),
+            Err(e) => {
+                log_error!("[Shell] Ignoring unreadable {}: {}", path.display(), e);
+                None
+            }
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            log_error!("[Shell] Could not read {}: {}", path.display(), e);
+            None
+        }
+    });
+    chosen.or_else(|| workspace::data_root_of(app, id).map(|dir| dir.join(DEFAULT_DIR_NAME)))
+}
+
+fn default_dir(app: &AppHandle) -> Option<PathBuf> {
+    workspace::data_root(app).map(|dir| dir.join(DEFAULT_DIR_NAME))
+}
+
+fn location_path(app: &AppHandle) -> Option<PathBuf> {
+    workspace::config_dir(app).map(|dir| dir.join(LOCATION_FILE))
+}
+
+/// Remember `dir` as the data folder; the default is stored as no choice.
+fn save_location(app: &AppHandle, dir: &Path) -> io::Result<()> {
+    let path = location_path(app)
+        .ok_or_else(|| io::Error::other("no config directory on this platform"))?;
+    if default_dir(app).as_deref() == Some(dir) {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let location = Location {
+        path: dir.to_path_buf(),
+    };
+    fs::write(&path, serde_json::to_vec_pretty(&location)?)
+}
+
+/// Files in `dir` and its subfolders that belong to the backend.
+fn data_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    Ok(files)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let ignored = IGNORED_FILES
+            .iter()
+            .any(|name| entry.file_name() == *name);
+        if file_type.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else if file_type.is_file() && !ignored {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Remove `dir` and the folders below it, as far as they are empty.
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+    let _ = fs::remove_dir(dir);
+}
+
+/// Copy the backend's files from `from` to `to`, then delete the originals.
+///
+/// Nothing is deleted unless every copy succeeded and has the original's
+/// size; on failure the copies made so far are removed again.
+fn move_files(from: &Path, to: &Path) -> Result<(), String> {
+    let files = data_files(from).map_err(|e| format!("Could not list {}: {}", from.display(), e))?;
+    fs::create_dir_all(to).map_err(|e| format!("Could not create {}: {}", to.display(), e))?;
+
+    let targets: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.strip_prefix(from).ok().map(|path| to.join(path)))
+        .collect();
+    if let Some(existing) = targets.iter().find(|target| target.exists()) {
+        return Err(format!(
+            "{} already exists; choose an empty folder or move it away first.",
+            existing.display()
+        ));
+    }
+
+    let mut copied = Vec::new();
+    for (file, target) in files.iter().zip(&targets) {
+        let parent = target.parent().unwrap_or(to);
+        let result = fs::create_dir_all(parent)
+            .and_then(|_| fs::copy(file, target))
+            .and_then(|size| {
+                copied.push(target.clone());
+                if size == fs::metadata(file)?.len() {
+                    Ok(())
+                } else {
+                    Err(io::Error::other("copy is incomplete"))
+                }
+            });
+        if let Err(e) = result {
+            for copy in &copied {
+                let _ = fs::remove_file(copy);
+            }
+            return Err(format!("Could not copy {}: {}", file.display(), e));
+        }
+    }
+
+    for file in &files {
+        if let Err(e) = fs::remove_file(file) {
+            // The copy is already in place, so this only costs disk space
+            log_error!("[Shell] Could not remove {} after moving it: {}", file.display(), e);
+        }
+    }
+    // Only removes folders that are now empty, which is what we want
+    remove_empty_dirs(from);
+    Ok(())
+}
+
+/// Move the data folder to `to`, stopping the sidecar while files move and
+/// starting it again afterwards (on whichever folder is current by then).
+fn relocate(app: &AppHandle, to: PathBuf) -> Result<PathBuf, String> {
+    if !to.is_absolute() {
+        return Err("Choose a full path for the data folder.".into());
+    }
+    let data_dir = app.state::<DataDir>();
+    let from = data_dir.path();
+    if from.as_deref() == Some(to.as_path()) {
+        return Ok(to);
+    }
+    if let Some(from) = &from {
+        if to.starts_with(from) {
+            return Err("The new folder can't be inside the current one.".into());
+        }
+    }
+
+    let sidecar_running = {
+        let state = backend::state(app);
+        let state = state.lock().unwrap();
+        state.child.is_some() && state.external_url.is_none()
+    };
+    if sidecar_running {
+        shutdown::shutdown_backend(app);
+    }
+
+    let result = match &from {
+        Some(from) => move_files(from, &to),
+        None => fs::create_dir_all(&to).map_err(|e| e.to_string()),
+    }
+    .and_then(|_| {
+        save_location(app, &to).map_err(|e| format!("Could not remember the new folder: {}", e))
+    });
+    if result.is_ok() {
+        log_info!("[Shell] Data folder moved to {}", to.display());
+        *data_dir.0.lock().unwrap() = Some(to.clone());
+    }
+
+    if sidecar_running {
+        backend::reset_for_retry(app);
+        if let Err(e) = backend::start_sidecar(app) {
+            health::fail(app, e);
+        }
+    }
+    result.map(|_| to)
+}
+
+fn info(app: &AppHandle) -> DataDirInfo {
+    let path = app.state::<DataDir>().path();
+    let default_path = default_dir(app);
+    let size = path
+        .as_deref()
+        .and_then(|dir| data_files(dir).ok())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|file| fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    DataDirInfo {
+        chain_exists: path.as_ref().is_some_and(|dir| dir.join(CHAIN_FILE).exists()),
+        is_default: path == default_path,
+        path,
+        default_path,
+        size,
+    }
+}
+
+#[tauri::command]
+pub fn data_dir_info(app: AppHandle) -> DataDirInfo {
+    info(&app)
+}
+
+#[tauri::command]
+pub fn reveal_data_dir(data_dir: tauri::State<'_, DataDir>) -> Result<(), String> {
+    let dir = data_dir.path().ok_or("No data folder on this platform")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    logs::reveal(&dir).map_err(|e| format!("Could not open {}: {}", dir.display(), e))
+}
+
+/// Ask for a folder with the native picker; `None` if the user cancelled.
+#[tauri::command]
+pub async fn pick_data_dir(app: AppHandle) -> Result<Option<PathBuf>, String> {
+    let start = app.state::<DataDir>().path();
+    let (tx, rx) = mpsc::channel();
+    // Dialogs belong on the main thread
+    app.run_on_main_thread(move || {
+        let mut dialog = rfd::FileDialog::new().set_title("Choose a folder for NatLangChain data");
+        if let Some(dir) = start.as_deref().and_then(Path::parent) {
+            dialog = dialog.set_directory(dir);
+        }
+        let _ = tx.send(dialog.pick_folder());
+    })
+    .map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || rx.recv().unwrap_or(None))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Move the chain and its backups to `path`, restarting the sidecar.
+#[tauri::command]
+pub async fn relocate_data_dir(app: AppHandle, path: PathBuf) -> Result<DataDirInfo, String> {
+    // Stopping the sidecar blocks until it has saved the chain
+    tauri::async_runtime::spawn_blocking(move || {
+        relocate(&app, path)?;
+        Ok(info(&app))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Move the data back to the default folder.
+#[tauri::command]
+pub async fn reset_data_dir(app: AppHandle) -> Result<DataDirInfo, String> {
+    let default = default_dir(&app).ok_or("No data folder on this platform")?;
+    relocate_data_dir(app, default).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nlc-datadir-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn moves_subfolders_along_with_the_chain() {
+        let root = temp_dir("nested");
+        let from = root.join("old");
+        let to = root.join("new");
+        fs::create_dir_all(from.join("models/cache")).unwrap();
+        fs::write(from.join(CHAIN_FILE), "{}").unwrap();
+        fs::write(from.join("models/cache/weights.bin"), "weights").unwrap();
+        fs::create_dir_all(from.join("empty")).unwrap();
+
+        move_files(&from, &to).unwrap();
+        assert_eq!(fs::read_to_string(to.join(CHAIN_FILE)).unwrap(), "{}");
+        let weights = fs::read_to_string(to.join("models/cache/weights.bin")).unwrap();
+        assert_eq!(weights, "weights");
+        assert!(!from.exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn leaves_ignored_files_behind() {
+        let root = temp_dir("ignored");
+        let from = root.join("old");
+        let to = root.join("new");
+        fs::create_dir_all(&from).unwrap();
+        fs::write(from.join(CHAIN_FILE), "{}").unwrap();
+        fs::write(from.join(".write-test"), "").unwrap();
+
+        assert_eq!(data_files(&from).unwrap(), vec![from.join(CHAIN_FILE)]);
+        move_files(&from, &to).unwrap();
+        assert!(!to.join(".write-test").exists());
+        assert!(from.join(".write-test").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn refuses_to_overwrite_files_in_the_new_folder() {
+        let root = temp_dir("existing");
+        let from = root.join("old");
+        let to = root.join("new");
+        fs::create_dir_all(from.join("backups")).unwrap();
+        fs::create_dir_all(to.join("backups")).unwrap();
+        fs::write(from.join(CHAIN_FILE), "{}").unwrap();
+        fs::write(from.join("backups/1.json"), "old").unwrap();
+        fs::write(to.join("backups/1.json"), "new").unwrap();
+
+        assert!(move_files(&from, &to).is_err());
+        assert!(from.join(CHAIN_FILE).exists());
+        assert!(!to.join(CHAIN_FILE).exists());
+        assert_eq!(
+            fs::read_to_string(to.join("backups/1.json")).unwrap(),
+            "new"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+//! AES-256-GCM with passphrase-derived keys, in the format of
+//! `src/encryption.py`.
+//!
+//! Encrypted data is `ENC:1:` followed by the base64 of a random salt (16
+//! bytes), a random IV (12 bytes) and the ciphertext with its GCM tag. The
+//! key is derived from the passphrase with PBKDF2-HMAC-SHA256 over the salt.
+//! Data encrypted here can be read by `decrypt_data` and the other way round.
+//!
+//! The backend encrypts sensitive metadata fields this way, and whole chain
+//! files when `NATLANGCHAIN_ENCRYPTION_KEY` is set (see `chainfile`).
+
+use std::fmt;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use tauri::AppHandle;
+
+use crate::settings;
+
+pub const ENCRYPTED_PREFIX: &str = "ENC:1:";
+/// Same minimum as the backend's `MIN_KEY_LENGTH`.
+pub const MIN_KEY_LENGTH: usize = 16;
+
+const SALT_SIZE: usize = 16;
+const IV_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 1_000_000;
+
+#[derive(Debug)]
+pub enum EncryptionError {
+    /// Not something `encrypt_data` wrote.
+    Format(&'static str),
+    /// The tag didn't match: a wrong passphrase or damaged data.
+    WrongKey,
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptionError::Format(reason) => write!(f, "Invalid encrypted data: {}.", reason),
+            EncryptionError::WrongKey => {
+                f.write_str("Decryption failed: the key is wrong or the data is damaged.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Aes256Gcm {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// `encrypt_data(data, passphrase)` for bytes.
+pub fn encrypt(data: &[u8], passphrase: &str) -> Result<String, EncryptionError> {
+    let mut salt = [0u8; SALT_SIZE];
+    let mut iv = [0u8; IV_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut iv);
+    let ciphertext = cipher(passphrase, &salt)
+        .encrypt(Nonce::from_slice(&iv), data)
+        .map_err(|_| EncryptionError::Format("too large to encrypt"))?;
+    let mut blob = Vec::with_capacity(SALT_SIZE + IV_SIZE + ciphertext.len());
+    blob.extend_from_slice(&salt);
+    blob.extend_from_slice(&iv);
+    blob.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(blob)))
+}
+
+/// `decrypt_data(encrypted, passphrase, return_type="bytes")`
+pub fn decrypt(encrypted: &str, passphrase: &str) -> Result<Vec<u8>, EncryptionError> {
+    let encoded = encrypted
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .ok_or(EncryptionError::Format("missing prefix"))?;
+    let blob = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| EncryptionError::Format("not base64"))?;
+    if blob.len() < SALT_SIZE + IV_SIZE + TAG_SIZE {
+        return Err(EncryptionError::Format("too short"));
+    }
+    let (salt, rest) = blob.split_at(SALT_SIZE);
+    let (iv, ciphertext) = rest.split_at(IV_SIZE);
+    cipher(passphrase, salt)
+        .decrypt(Nonce::from_slice(iv), ciphertext)
+        .map_err(|_| EncryptionError::WrongKey)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DecryptedFields {
+    pub metadata: Map<String, Value>,
+    /// Fields that kept their encrypted value because the key didn't fit.
+    pub failed: Vec<String>,
+}
+
+/// `decrypt_sensitive_fields`: the metadata with its `ENC:1:` fields
+/// decrypted. Fields that were encrypted from JSON come back as JSON. As in
+/// the backend, a field that can't be decrypted keeps its encrypted value;
+/// `failed` names those, so the user can tell.
+pub fn decrypt_fields(metadata: &Map<String, Value>, passphrase: &str) -> DecryptedFields {
+    let mut result = Map::new();
+    let mut failed = Vec::new();
+    for (field, value) in metadata {
+        // Markers `encrypt_sensitive_fields` leaves when encryption failed
+        if field.starts_with("__") && field.ends_with("_encryption_failed") {
+            continue;
+        }
+        let value = match value.as_str().filter(|v| v.starts_with(ENCRYPTED_PREFIX)) {
+            Some(encrypted) => match decrypt(encrypted, passphrase) {
+                Ok(plain) => field_value(plain),
+                Err(_) => {
+                    failed.push(field.clone());
+                    value.clone()
+                }
+            },
+            None => value.clone(),
+        };
+        result.insert(field.clone(), value);
+    }
+    DecryptedFields {
+        metadata: result,
+        failed,
+    }
+}
+
+fn field_value(plain: Vec<u8>) -> Value {
+    let text = String::from_utf8_lossy(&plain).into_owned();
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+/// `key` if one was given, otherwise the current workspace's.
+pub fn key_or_default(app: &AppHandle, key: Option<String>) -> Option<String> {
+    key.filter(|key| !key.is_empty()).or_else(|| settings::encryption_key(app))
+}
+
+/// Decrypt the sensitive fields of an entry's metadata with `key`, or with
+/// the current workspace's encryption key.
+#[tauri::command]
+pub async fn decrypt_metadata(
+    app: AppHandle,
+    metadata: Map<String, Value>,
+    key: Option<String>,
+) -> Result<DecryptedFields, String> {
+    let key = key_or_default(&app, key).ok_or("No encryption key is set for this workspace.")?;
+    // Every field has its own salt, so each one costs a full key derivation
+    tauri::async_runtime::spawn_blocking(move || decrypt_fields(&metadata, &key))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Encrypted by `encrypt_sensitive_fields`; see tests/fixtures/generate.py
+    const METADATA: &str = include_str!("../tests/fixtures/encrypted_metadata.json");
+
+    fn fixture() -> (String, Map<String, Value>, Map<String, Value>) {
+        let mut fixture: Value = serde_json::from_str(METADATA).unwrap();
+        let key = fixture["key"].as_str().unwrap().to_string();
+        let plain = serde_json::from_value(fixture["plain"].take()).unwrap();
+        let encrypted = serde_json::from_value(fixture["encrypted"].take()).unwrap();
+        (key, plain, encrypted)
+    }
+
+    #[test]
+    fn decrypts_backend_fields() {
+        let (key, plain, encrypted) = fixture();
+        let decrypted = decrypt_fields(&encrypted, &key);
+        assert!(decrypted.failed.is_empty());
+        assert_eq!(decrypted.metadata, plain);
+    }
+
+    #[test]
+    fn keeps_fields_the_key_does_not_fit() {
+        let (_, _, encrypted) = fixture();
+        let mut decrypted = decrypt_fields(&encrypted, "not the fixture key");
+        decrypted.failed.sort();
+        assert_eq!(decrypted.failed, ["payment_amount", "private_notes"]);
+        assert_eq!(decrypted.metadata, encrypted);
+    }
+
+    #[test]
+    fn round_trips_and_rejects_malformed_data() {
+        let encrypted = encrypt("caf\u{e9} \u{1F4B0}".as_bytes(), "a shell-side passphrase").unwrap();
+        assert!(encrypted.starts_with(ENCRYPTED_PREFIX));
+        let plain = decrypt(&encrypted, "a shell-side passphrase").unwrap();
+        assert_eq!(plain, "caf\u{e9} \u{1F4B0}".as_bytes());
+
+        assert!(matches!(decrypt("plain text", "key"), Err(EncryptionError::Format(_))));
+        assert!(matches!(decrypt("ENC:1:@@@", "key"), Err(EncryptionError::Format(_))));
+        assert!(matches!(decrypt("ENC:1:AAAA", "key"), Err(EncryptionError::Format(_))));
+    }
+}
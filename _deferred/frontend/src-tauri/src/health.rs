@@ -0,0 +1,253 @@
+//! Readiness gating: keeps the splash screen up until the backend answers
+//! `/health/ready` (or, for an external backend, `/health`), then reveals the
+//! main window.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{auth, compat};
+use crate::backend::{self, BackendState, BackendStatus};
+use crate::startup::{self, StartupError};
+
+/// PyInstaller one-file builds unpack themselves before Flask even starts,
+/// so cold starts can take a while on slow disks.
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+/// An external backend is expected to be up already, so it gets one try.
+const EXTERNAL_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const MAIN_WINDOW: &str = "main";
+pub const SPLASH_WINDOW: &str = "splashscreen";
+
+pub const EVENT_READY: &str = "backend://ready";
+pub const EVENT_FAILED: &str = "backend://failed";
+
+/// What Python prints when the port was taken between our check and its bind.
+const ADDRESS_IN_USE: &[&str] = &[
+    "Address already in use",
+    "Only one usage of each socket address",
+];
+
+#[derive(Clone, serde::Serialize)]
+struct FailedPayload {
+    kind: &'static str,
+    message: String,
+    stderr: Vec<String>,
+}
+
+/// Poll the backend until it reports ready, the process dies, or we time out.
+///
+/// `state` is the backend of the workspace being started and `generation`
+/// the spawn being polled; the poller quietly stops once the supervisor has
+/// replaced that process.
+pub async fn wait_for_ready(app: AppHandle, state: Arc<Mutex<BackendState>>, generation: u64) {
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            fail(&app, StartupError::NotReady(format!("Could not create HTTP client: {}", e)));
+            return;
+        }
+    };
+    let url = format!("{}/health/ready", state.lock().unwrap().base_url());
+    let deadline = Instant::now() + READY_TIMEOUT;
+
+    loop {
+        {
+            let state = state.lock().unwrap();
+            if state.generation != generation {
+                return;
+            }
+            if state.child.is_none() {
+                // A scheduled restart will start its own poller
+                if state.status != BackendStatus::Failed {
+                    return;
+                }
+                let port_taken = state
+                    .stderr_tail
+                    .iter()
+                    .any(|line| ADDRESS_IN_USE.iter().any(|needle| line.contains(needle)));
+                let error = if port_taken {
+                    StartupError::PortUnavailable(format!("port {} is already in use", state.port))
+                } else {
+                    StartupError::NotReady(
+                        "The backend process exited before it became ready.".into(),
+                    )
+                };
+                drop(state);
+                fail(&app, error);
+                return;
+            }
+        }
+
+        if let Ok(response) = client.get(&url).send().await {
+            if response.status().is_success() {
+                let (base_url, api_token) = {
+                    let state = state.lock().unwrap();
+                    (state.base_url(), state.api_token.clone())
+                };
+                match compat::handshake(&client, &base_url, &api_token).await {
+                    Ok(report) => {
+                        compat::apply(&app, report);
+                        mark_ready(&app, &state, generation);
+                    }
+                    Err(e) => fail(&app, e),
+                }
+                return;
+            }
+        }
+
+        if Instant::now() >= deadline {
+            fail(
+                &app,
+                StartupError::NotReady(format!(
+                    "The backend did not become ready within {} seconds.",
+                    READY_TIMEOUT.as_secs()
+                )),
+            );
+            return;
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Validate an external backend via `/health` and `/version` and use it
+/// instead of the sidecar, falling back to spawning the sidecar if `fallback`
+/// is set and the backend can't be reached.
+pub async fn connect_external(
+    app: AppHandle,
+    url: String,
+    api_key: Option<String>,
+    fallback: bool,
+) {
+    let key = api_key.unwrap_or_default();
+    let (client, version) = match probe_external(&url, &key).await {
+        Ok(probed) => probed,
+        Err(e) if fallback => {
+            log_error!(
+                "[Shell] External backend at {} unavailable ({}), starting the sidecar",
+                url,
+                e
+            );
+            if let Err(e) = backend::start_sidecar(&app) {
+                fail(&app, e);
+            }
+            return;
+        }
+        Err(reason) => {
+            fail(&app, StartupError::ExternalUnavailable { url, reason });
+            return;
+        }
+    };
+
+    let report = match compat::handshake(&client, &url, &key).await {
+        Ok(report) => report,
+        Err(e) => {
+            fail(&app, e);
+            return;
+        }
+    };
+
+    log_info!("[Shell] Using external backend at {} (version {})", url, version);
+    compat::apply(&app, report);
+    let state = backend::state(&app);
+    let generation = {
+        let mut state = state.lock().unwrap();
+        state.external_url = Some(url);
+        state.api_token = key;
+        state.generation
+    };
+    mark_ready(&app, &state, generation);
+}
+
+/// Check that `url` is a healthy NatLangChain backend; returns the client
+/// used and the backend's version.
+async fn probe_external(url: &str, api_key: &str) -> Result<(reqwest::Client, String), String> {
+    let client = reqwest::Client::builder()
+        .timeout(EXTERNAL_PROBE_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.get(format!("{}/health", url));
+    if !api_key.is_empty() {
+        request = request.header(auth::HEADER, api_key);
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("/health returned {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("/health did not return JSON: {}", e))?;
+    if body["status"] != "healthy" {
+        return Err(format!("/health reports status {}", body["status"]));
+    }
+    let version = body["version"].as_str().unwrap_or("unknown").to_string();
+    Ok((client, version))
+}
+
+fn mark_ready(app: &AppHandle, state: &Arc<Mutex<BackendState>>, generation: u64) {
+    {
+        let mut state = state.lock().unwrap();
+        let alive = state.child.is_some() || state.external_url.is_some();
+        if state.generation != generation || !alive {
+            return;
+        }
+        state.status = BackendStatus::Ready;
+    }
+    backend::emit_status(app);
+
+    if let Some(main) = app.get_window(MAIN_WINDOW) {
+        let _ = main.show();
+        let _ = main.set_focus();
+    }
+    if let Some(splash) = app.get_window(SPLASH_WINDOW) {
+        let _ = splash.close();
+    }
+
+    let _ = app.emit_all(EVENT_READY, ());
+}
+
+/// Put the splash back into its loading state for another attempt.
+pub fn show_starting(app: &AppHandle) {
+    if let Some(splash) = app.get_window(SPLASH_WINDOW) {
+        let _ = splash.eval("window.showStarting()");
+    }
+}
+
+/// Report a failed start on the splash screen and offer recovery options.
+pub fn fail(app: &AppHandle, error: StartupError) {
+    let message = error.to_string();
+    log_error!("[Shell] {}", message);
+    let stderr: Vec<String> = backend::state(app)
+        .lock()
+        .unwrap()
+        .stderr_tail
+        .iter()
+        .cloned()
+        .collect();
+
+    if let Some(splash) = app.get_window(SPLASH_WINDOW) {
+        // The splash is undecorated while loading; give it a close button now
+        let _ = splash.set_decorations(true);
+        let script = format!(
+            "window.showStartupError({}, {})",
+            serde_json::to_string(&message).unwrap_or_default(),
+            serde_json::to_string(&stderr).unwrap_or_default()
+        );
+        let _ = splash.eval(&script);
+    }
+
+    let payload = FailedPayload {
+        kind: error.kind(),
+        message,
+        stderr,
+    };
+    let _ = app.emit_all(EVENT_FAILED, payload);
+
+    startup::show_recovery_dialog(app, &error);
+}
@@ -0,0 +1,158 @@
+//! Single-instance enforcement.
+//!
+//! Two shells would mean two sidecars writing the same chain file. The first
+//! instance holds an exclusive lock on `instance.lock` in the app data dir
+//! and listens on a loopback port recorded in `instance.port` (a separate
+//! file, since Windows won't let anyone read a locked one). A second launch
+//! sends its arguments there and exits before building the app, so it never
+//! spawns a backend.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use fs2::FileExt;
+use tauri::{AppHandle, Manager};
+
+use crate::health;
+
+pub const EVENT_SECOND_INSTANCE: &str = "app://second-instance";
+
+/// How long a second launch keeps trying to reach a first instance that
+/// holds the lock but hasn't published its port yet.
+const FORWARD_ATTEMPTS: u32 = 20;
+const FORWARD_RETRY_DELAY: Duration = Duration::from_millis(100);
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Arguments of a launch that was folded into this instance.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct SecondLaunch {
+    pub args: Vec<String>,
+    pub cwd: String,
+}
+
+impl SecondLaunch {
+    fn current() -> Self {
+        Self {
+            args: std::env::args().skip(1).collect(),
+            cwd: std::env::current_dir()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The lock and listener of the primary instance; both live until exit.
+pub struct Instance {
+    lock: File,
+    listener: TcpListener,
+}
+
+/// Become the primary instance, or hand this launch to the existing one.
+///
+/// Returns `Ok(None)` if another instance is running and this process should
+/// exit. That holds even if forwarding fails: a second backend is worse than
+/// a lost argument.
+pub fn acquire(dir: &Path) -> io::Result<Option<Instance>> {
+    fs::create_dir_all(dir)?;
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("instance.lock"))?;
+
+    if lock.try_lock_exclusive().is_err() {
+        if let Err(e) = forward(dir) {
+            log_error!("[Shell] Running instance did not take the launch: {}", e);
+        }
+        return Ok(None);
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    fs::write(dir.join("instance.port"), port.to_string())?;
+
+    Ok(Some(Instance { lock, listener }))
+}
+
+fn forward(dir: &Path) -> io::Result<()> {
+    let launch = serde_json::to_string(&SecondLaunch::current())?;
+    let mut last_error = None;
+
+    for _ in 0..FORWARD_ATTEMPTS {
+        let result = fs::read_to_string(dir.join("instance.port"))
+            .and_then(|port| {
+                port.trim()
+                    .parse::<u16>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+            .and_then(|port| send(port, &launch));
+        match result {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+        thread::sleep(FORWARD_RETRY_DELAY);
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::other("no running instance answered")))
+}
+
+fn send(port: u16, launch: &str) -> io::Result<()> {
+    let addr = (Ipv4Addr::LOCALHOST, port).into();
+    let mut stream = TcpStream::connect_timeout(&addr, FORWARD_TIMEOUT)?;
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    writeln!(stream, "{}", launch)?;
+
+    // Wait for the acknowledgement so we know it was a NatLangChain shell
+    // listening on that port and not whatever reused it
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    if reply.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected reply from running instance"))
+    }
+}
+
+impl Instance {
+    /// Accept forwarded launches for the rest of the process's life.
+    pub fn listen(self, app: AppHandle) {
+        let Instance { lock, listener } = self;
+        thread::spawn(move || {
+            // Held (and so locked) until the process exits
+            let _lock = lock;
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                if let Err(e) = receive(&app, stream) {
+                    log_error!("[Shell] Ignoring malformed second-launch message: {}", e);
+                }
+            }
+        });
+    }
+}
+
+fn receive(app: &AppHandle, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let launch: SecondLaunch = serde_json::from_str(&line)?;
+    writeln!(stream, "ok")?;
+
+    log_info!("[Shell] Second launch forwarded with args {:?}", launch.args);
+    focus(app);
+    let _ = app.emit_all(EVENT_SECOND_INSTANCE, launch);
+    Ok(())
+}
+
+/// Bring the main window forward, or the splash while the backend starts.
+fn focus(app: &AppHandle) {
+    let main = app
+        .get_window(health::MAIN_WINDOW)
+        .filter(|window| window.is_visible().unwrap_or(false));
+    if let Some(window) = main.or_else(|| app.get_window(health::SPLASH_WINDOW)) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
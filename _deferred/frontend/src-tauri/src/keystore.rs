@@ -0,0 +1,537 @@
+//! Agent identities kept by the shell.
+//!
+//! The backend signs entries with a single Ed25519 key read from the files
+//! named by `NATLANGCHAIN_IDENTITY_KEYSTORE` and
+//! `NATLANGCHAIN_IDENTITY_PASSPHRASE`. On the desktop the user instead keeps
+//! their keys here, in `identities.json` in the app data folder, shared by all
+//! workspaces. Each private key is encrypted with its own passphrase in the
+//! backend's `ENC:1:` format (see `encryption.rs`), and the file is readable
+//! only by the current user.
+//!
+//! An identity is unlocked once with its passphrase and then stays in memory
+//! until it is locked again or the app exits. Entries are signed the way
+//! `identity.sign_entry_dict` signs them, so `verify_entry_signature` accepts
+//! them. Keys can be imported from and exported to the PKCS#8 PEM files that
+//! `AgentIdentity.save` and `AgentIdentity.load` use.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{SecondsFormat, Utc};
+use ed25519_dalek::pkcs8::KeypairBytes;
+use ed25519_dalek::{Signer, SigningKey};
+use pkcs8::der::pem::PemLabel;
+use pkcs8::der::zeroize::Zeroizing;
+use pkcs8::pkcs5::pbes2;
+use pkcs8::{
+    DecodePrivateKey, EncodePrivateKey, EncryptedPrivateKeyInfo, LineEnding, PrivateKeyInfo,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::encryption::{self, EncryptionError, MIN_KEY_LENGTH};
+use crate::{settings, signatures};
+
+const KEYSTORE_FILE: &str = "identities.json";
+const MAX_NAME_LENGTH: usize = 64;
+/// PBKDF2 rounds for exported key files; `AgentIdentity.save` uses
+/// OpenSSL's 2048, which is far too few for a key left on disk
+const KEY_FILE_ITERATIONS: u32 = 600_000;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct StoredIdentity {
+    /// The key's fingerprint, as `AgentIdentity.fingerprint` computes it.
+    id: String,
+    name: String,
+    /// Base64, as it appears in signed entries.
+    public_key: String,
+    created: String,
+    /// The 32-byte private key, `ENC:1:`-encrypted with the passphrase.
+    private_key: String,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct KeystoreFile {
+    identities: Vec<StoredIdentity>,
+}
+
+/// Unlocked keys, by identity id.
+#[derive(Default)]
+pub struct Keystore {
+    unlocked: Mutex<HashMap<String, SigningKey>>,
+}
+
+/// What the UI sees of an identity; never the private key.
+#[derive(serde::Serialize)]
+pub struct IdentityInfo {
+    pub id: String,
+    pub name: String,
+    pub fingerprint: String,
+    pub public_key: String,
+    pub created: String,
+    pub unlocked: bool,
+}
+
+impl Keystore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn info(&self, identity: &StoredIdentity) -> IdentityInfo {
+        IdentityInfo {
+            id: identity.id.clone(),
+            name: identity.name.clone(),
+            fingerprint: identity.id.clone(),
+            public_key: identity.public_key.clone(),
+            created: identity.created.clone(),
+            unlocked: self.unlocked.lock().unwrap().contains_key(&identity.id),
+        }
+    }
+}
+
+fn keystore_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(KEYSTORE_FILE))
+}
+
+fn load(app: &AppHandle) -> Result<KeystoreFile, String> {
+    let path = keystore_path(app).ok_or("No data folder on this platform")?;
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("{} is damaged: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(KeystoreFile::default()),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+}
+
+fn save(app: &AppHandle, keystore: &KeystoreFile) -> Result<(), String> {
+    let path = keystore_path(app).ok_or("No data folder on this platform")?;
+    let write = || -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write then rename, so a crash never loses the keys
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(keystore)?)?;
+        settings::restrict_permissions(&temp)?;
+        fs::rename(&temp, &path)
+    };
+    write().map_err(|e| format!("Could not save {}: {}", path.display(), e))
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Give the identity a name.".into());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Keep the name under {} characters.", MAX_NAME_LENGTH));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_KEY_LENGTH {
+        return Err(format!("Use a passphrase of at least {} characters.", MIN_KEY_LENGTH));
+    }
+    Ok(())
+}
+
+fn find<'a>(keystore: &'a KeystoreFile, id: &str) -> Result<&'a StoredIdentity, String> {
+    keystore
+        .identities
+        .iter()
+        .find(|identity| identity.id == id)
+        .ok_or_else(|| format!("No identity \"{}\"", id))
+}
+
+/// Encrypt `key` and add it to the keystore under `name`.
+fn add(
+    app: &AppHandle,
+    key: &SigningKey,
+    name: &str,
+    passphrase: &str,
+) -> Result<IdentityInfo, String> {
+    let name = validate_name(name)?;
+    validate_passphrase(passphrase)?;
+    let public_key = key.verifying_key().to_bytes();
+    let id = signatures::fingerprint(&public_key);
+
+    let mut keystore = load(app)?;
+    if let Some(existing) = keystore.identities.iter().find(|identity| identity.id == id) {
+        return Err(format!("This key is already stored as \"{}\".", existing.name));
+    }
+    let identity = StoredIdentity {
+        id: id.clone(),
+        name,
+        public_key: STANDARD.encode(public_key),
+        created: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        private_key: encryption::encrypt(&key.to_bytes(), passphrase).map_err(|e| e.to_string())?,
+    };
+    keystore.identities.push(identity.clone());
+    save(app, &keystore)?;
+    log_info!("[Shell] Added identity {} ({})", identity.name, id);
+
+    let store = app.state::<Keystore>();
+    store.unlocked.lock().unwrap().insert(id, key.clone());
+    Ok(store.info(&identity))
+}
+
+/// Decrypt an identity's private key with its passphrase.
+fn decrypt_key(identity: &StoredIdentity, passphrase: &str) -> Result<SigningKey, String> {
+    let seed = encryption::decrypt(&identity.private_key, passphrase).map_err(|e| match e {
+        EncryptionError::WrongKey => "Wrong passphrase.".to_string(),
+        e => e.to_string(),
+    })?;
+    let seed: [u8; 32] = seed
+        .as_slice()
+        .try_into()
+        .map_err(|_| "The stored key is damaged.")?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+fn unlocked_key(app: &AppHandle, id: &str) -> Result<SigningKey, String> {
+    let store = app.state::<Keystore>();
+    let unlocked = store.unlocked.lock().unwrap();
+    unlocked
+        .get(id)
+        .cloned()
+        .ok_or_else(|| "Unlock the identity first.".to_string())
+}
+
+/// `datetime.utcnow().isoformat()`, the format the backend stamps entries with.
+fn utc_timestamp() -> String {
+    Utc::now().naive_utc().format("%Y-%m-%dT%H:%M:%S%.6f").to_string()
+}
+
+/// `sign_entry_dict`: sign the entry's content, author, intent and timestamp
+/// and add the signature, public key and fingerprint to its metadata. An
+/// entry without a timestamp gets the current time, which must then be
+/// submitted with it.
+fn sign(key: &SigningKey, mut entry: Value) -> Result<Value, String> {
+    let fields = entry.as_object_mut().ok_or("The entry is not a JSON object.")?;
+    if fields.get("timestamp").filter(|t| !t.is_null()).is_none() {
+        fields.insert("timestamp".into(), Value::String(utc_timestamp()));
+    }
+    let payload = signatures::canonical_payload(fields);
+    let signature = key.sign(payload.as_bytes());
+    let public_key = key.verifying_key().to_bytes();
+
+    let metadata = fields.entry("metadata").or_insert_with(|| json!({}));
+    if metadata.is_null() {
+        *metadata = json!({});
+    }
+    let metadata = metadata
+        .as_object_mut()
+        .ok_or("The entry's metadata is not a JSON object.")?;
+    metadata.insert("signature".into(), STANDARD.encode(signature.to_bytes()).into());
+    metadata.insert("public_key".into(), STANDARD.encode(public_key).into());
+    metadata.insert("signer_fingerprint".into(), signatures::fingerprint(&public_key).into());
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn list_identities(app: AppHandle) -> Result<Vec<IdentityInfo>, String> {
+    let store = app.state::<Keystore>();
+    Ok(load(&app)?.identities.iter().map(|identity| store.info(identity)).collect())
+}
+
+/// Create a new key, stored encrypted with `passphrase` and left unlocked.
+#[tauri::command]
+pub async fn generate_identity(
+    app: AppHandle,
+    name: String,
+    passphrase: String,
+) -> Result<IdentityInfo, String> {
+    // Key derivation takes a noticeable moment on purpose
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        add(&app, &SigningKey::from_bytes(&seed), &name, &passphrase)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Import a key file written by `AgentIdentity.save`. `file_passphrase` is
+/// the one it was saved with, if any; `passphrase` protects it from now on.
+#[tauri::command]
+pub async fn import_identity(
+    app: AppHandle,
+    path: PathBuf,
+    file_passphrase: Option<String>,
+    name: Option<String>,
+    passphrase: String,
+) -> Result<IdentityInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let pem = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let key = read_key_file(&pem, file_passphrase.as_deref())?;
+        // `AgentIdentity.load` also names identities after the file
+        let name = name.filter(|name| !name.trim().is_empty()).unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        add(&app, &key, &name, &passphrase)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Write an identity to `path` as a key file `AgentIdentity.load` can read,
+/// for use as the backend's `NATLANGCHAIN_IDENTITY_KEYSTORE`. The file is
+/// encrypted with `file_passphrase` if one is given.
+#[tauri::command]
+pub async fn export_identity(
+    app: AppHandle,
+    id: String,
+    passphrase: String,
+    path: PathBuf,
+    file_passphrase: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let keystore = load(&app)?;
+        let key = decrypt_key(find(&keystore, &id)?, &passphrase)?;
+        let pem = key_file(&key, file_passphrase.as_deref())
+            .map_err(|e| format!("Could not encode the key: {}", e))?;
+        write_key_file(&path, pem.as_bytes())
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        log_info!("[Shell] Exported identity {} to {}", id, path.display());
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// A key file as `AgentIdentity.save` writes it, which `load_pem_private_key`
+/// requires: a version 1 PKCS#8 document holding only the seed, encrypted
+/// (if `passphrase` is given) with PBES2 using PBKDF2-SHA256 and AES-256-CBC.
+/// pkcs8's own encoders add the public key and default to scrypt, which
+/// OpenSSL rejects.
+fn key_file(key: &SigningKey, passphrase: Option<&str>) -> Result<Zeroizing<String>, pkcs8::Error> {
+    let seed_only = KeypairBytes {
+        secret_key: key.to_bytes(),
+        public_key: None,
+    };
+    let Some(passphrase) = passphrase.filter(|p| !p.is_empty()) else {
+        return seed_only.to_pkcs8_pem(LineEnding::LF);
+    };
+    let der = seed_only.to_pkcs8_der()?;
+    let mut salt = [0u8; 16];
+    let mut iv = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut iv);
+    let params = pbes2::Parameters::pbkdf2_sha256_aes256cbc(KEY_FILE_ITERATIONS, &salt, &iv)?;
+    let encrypted =
+        PrivateKeyInfo::try_from(der.as_bytes())?.encrypt_with_params(params, passphrase)?;
+    Ok(encrypted.to_pem(EncryptedPrivateKeyInfo::PEM_LABEL, LineEnding::LF)?)
+}
+
+/// Read a key file written by `AgentIdentity.save` (or `key_file`).
+fn read_key_file(pem: &str, passphrase: Option<&str>) -> Result<SigningKey, String> {
+    match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => SigningKey::from_pkcs8_encrypted_pem(pem, passphrase),
+        None => SigningKey::from_pkcs8_pem(pem),
+    }
+    .map_err(|_| {
+        "Not an Ed25519 key file, or the file's passphrase is wrong or missing.".to_string()
+    })
+}
+
+fn write_key_file(path: &Path, pem: &[u8]) -> io::Result<()> {
+    fs::write(path, pem)?;
+    settings::restrict_permissions(path)
+}
+
+/// Forget an identity and its key. There is no undo unless it was exported.
+#[tauri::command]
+pub fn delete_identity(app: AppHandle, id: String) -> Result<(), String> {
+    let mut keystore = load(&app)?;
+    let before = keystore.identities.len();
+    keystore.identities.retain(|identity| identity.id != id);
+    if keystore.identities.len() == before {
+        return Err(format!("No identity \"{}\"", id));
+    }
+    save(&app, &keystore)?;
+    app.state::<Keystore>().unlocked.lock().unwrap().remove(&id);
+    log_info!("[Shell] Deleted identity {}", id);
+    Ok(())
+}
+
+/// Decrypt an identity's key and keep it in memory for signing.
+#[tauri::command]
+pub async fn unlock_identity(
+    app: AppHandle,
+    id: String,
+    passphrase: String,
+) -> Result<IdentityInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let keystore = load(&app)?;
+        let identity = find(&keystore, &id)?;
+        let key = decrypt_key(identity, &passphrase)?;
+        let store = app.state::<Keystore>();
+        store.unlocked.lock().unwrap().insert(id, key);
+        Ok(store.info(identity))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn lock_identity(keystore: tauri::State<'_, Keystore>, id: String) {
+    keystore.unlocked.lock().unwrap().remove(&id);
+}
+
+/// Sign `entry` with an unlocked identity. Resolves to the entry to submit,
+/// with its timestamp and the signature fields in its metadata.
+#[tauri::command]
+pub fn sign_entry(app: AppHandle, id: String, entry: Value) -> Result<Value, String> {
+    sign(&unlocked_key(&app, &id)?, entry)
+}
+
+/// Ask for a key file with the native picker, to import from or (with
+/// `save`) export to; `None` if the user cancelled.
+#[tauri::command]
+pub async fn pick_identity_file(app: AppHandle, save: bool) -> Result<Option<PathBuf>, String> {
+    let (tx, rx) = mpsc::channel();
+    // Dialogs belong on the main thread
+    app.run_on_main_thread(move || {
+        let dialog = rfd::FileDialog::new().add_filter("Key files", &["pem", "key"]);
+        let path = if save {
+            dialog
+                .set_title("Export identity")
+                .set_file_name("identity.pem")
+                .save_file()
+        } else {
+            dialog.set_title("Import identity").pick_file()
+        };
+        let _ = tx.send(path);
+    })
+    .map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || rx.recv().unwrap_or(None))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use pkcs8::pkcs5::pbes2::{EncryptionScheme, Pbkdf2Prf};
+    use pkcs8::{SecretDocument, Version};
+
+    use super::*;
+
+    // Written by `AgentIdentity.save`; see tests/fixtures/generate.py
+    const SAVED: &str = include_str!("../tests/fixtures/identity.pem");
+    const SAVED_ENCRYPTED: &str = include_str!("../tests/fixtures/identity_encrypted.pem");
+    const SAVED_PUBLIC_KEY: &str = include_str!("../tests/fixtures/identity.pub");
+    const SAVED_PASSPHRASE: &str = "fixture passphrase";
+
+    const EXPORT_PASSPHRASE: &str = "export passphrase";
+
+    fn public_key(key: &SigningKey) -> String {
+        STANDARD.encode(key.verifying_key().to_bytes())
+    }
+
+    #[test]
+    fn reads_agent_identity_save_files() {
+        let plain = read_key_file(SAVED, None).unwrap();
+        assert_eq!(public_key(&plain), SAVED_PUBLIC_KEY.trim());
+        let encrypted = read_key_file(SAVED_ENCRYPTED, Some(SAVED_PASSPHRASE)).unwrap();
+        assert_eq!(public_key(&encrypted), SAVED_PUBLIC_KEY.trim());
+        assert!(read_key_file(SAVED_ENCRYPTED, Some("not the passphrase")).is_err());
+        assert!(read_key_file(SAVED_ENCRYPTED, None).is_err());
+    }
+
+    #[test]
+    fn exports_only_the_seed() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let pem = key_file(&key, None).unwrap();
+        let (_, document) = SecretDocument::from_pem(&pem).unwrap();
+        let info = PrivateKeyInfo::try_from(document.as_bytes()).unwrap();
+        assert_eq!(info.version(), Version::V1);
+        assert!(info.public_key.is_none());
+        assert_eq!(
+            read_key_file(&pem, None).unwrap().to_bytes(),
+            key.to_bytes()
+        );
+    }
+
+    #[test]
+    fn encrypts_exports_the_way_openssl_reads_them() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let pem = key_file(&key, Some(EXPORT_PASSPHRASE)).unwrap();
+        let (label, document) = SecretDocument::from_pem(&pem).unwrap();
+        assert_eq!(label, EncryptedPrivateKeyInfo::PEM_LABEL);
+        let info = EncryptedPrivateKeyInfo::try_from(document.as_bytes()).unwrap();
+        let params = info.encryption_algorithm.pbes2().unwrap();
+        let kdf = params.kdf.pbkdf2().unwrap();
+        assert_eq!(kdf.prf, Pbkdf2Prf::HmacWithSha256);
+        assert_eq!(kdf.iteration_count, KEY_FILE_ITERATIONS);
+        assert!(matches!(
+            params.encryption,
+            EncryptionScheme::Aes256Cbc { .. }
+        ));
+        let read = read_key_file(&pem, Some(EXPORT_PASSPHRASE)).unwrap();
+        assert_eq!(read.to_bytes(), key.to_bytes());
+    }
+
+    /// The real test: `AgentIdentity.load`, when Python and `cryptography`
+    /// are installed
+    #[test]
+    fn agent_identity_load_reads_exports() {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../../src");
+        let python = Command::new("python3")
+            .args(["-c", "import cryptography"])
+            .status()
+            .is_ok_and(|status| status.success());
+        if !python || !src.join("identity.py").exists() {
+            eprintln!("skipped: needs python3 with cryptography and the backend's src/");
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("nlc-keystore-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        for (name, passphrase) in [
+            ("plain.pem", None),
+            ("encrypted.pem", Some(EXPORT_PASSPHRASE)),
+        ] {
+            let path = dir.join(name);
+            write_key_file(&path, key_file(&key, passphrase).unwrap().as_bytes()).unwrap();
+            let output = Command::new("python3")
+                .current_dir(&src)
+                .arg("-c")
+                .arg(
+                    "import sys\n\
+                     from identity import AgentIdentity\n\
+                     identity = AgentIdentity.load(sys.argv[1], passphrase=sys.argv[2] or None)\n\
+                     print(identity.public_key_b64)",
+                )
+                .arg(&path)
+                .arg(passphrase.unwrap_or_default())
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout).trim(),
+                public_key(&key)
+            );
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
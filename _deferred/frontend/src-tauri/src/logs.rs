@@ -0,0 +1,338 @@
+//! Rotating log files for the shell and the backend sidecar.
+//!
+//! Release builds on Windows have no console, so everything that used to be
+//! `println!`-ed also goes to `backend.log` / `shell.log` in the app's log
+//! directory, one JSON record per line. Files rotate to `<name>.1`,
+//! `<name>.2`, ... once they reach `MAX_FILE_SIZE`; rotated files beyond
+//! `MAX_ROTATED` or older than `MAX_AGE` are deleted.
+//!
+//! The writers live in a global rather than managed state so that code
+//! without an `AppHandle` at hand can log too. Until `init` runs (or if the
+//! log directory is unusable) logging goes to the console only.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use chrono::{SecondsFormat, Utc};
+
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_ROTATED: u32 = 5;
+const MAX_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// Upper bound for `tail_log`, so the webview can't ask for a whole file.
+const MAX_TAIL_LINES: usize = 5000;
+
+static LOGS: OnceLock<Logs> = OnceLock::new();
+
+/// `println!` that also lands in `shell.log`.
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        $crate::logs::shell($crate::logs::Stream::Stdout, format!($($arg)*))
+    };
+}
+
+/// `eprintln!` that also lands in `shell.log`.
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        $crate::logs::shell($crate::logs::Stream::Stderr, format!($($arg)*))
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFile {
+    Backend,
+    Shell,
+}
+
+impl LogFile {
+    fn file_name(self) -> &'static str {
+        match self {
+            LogFile::Backend => "backend.log",
+            LogFile::Shell => "shell.log",
+        }
+    }
+}
+
+/// One line of `backend.log` / `shell.log`.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LogRecord {
+    /// RFC 3339, UTC, millisecond precision.
+    pub timestamp: String,
+    pub stream: Stream,
+    /// The sidecar's pid in `backend.log`, the shell's own in `shell.log`.
+    pub pid: u32,
+    pub message: String,
+}
+
+impl LogRecord {
+    pub fn now(stream: Stream, pid: u32, message: String) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            stream,
+            pid,
+            message,
+        }
+    }
+}
+
+struct Logs {
+    dir: PathBuf,
+    backend: Mutex<RotatingFile>,
+    shell: Mutex<RotatingFile>,
+}
+
+/// Open (or create) the log files in `dir`. Only the first call has any effect.
+pub fn init(dir: PathBuf) -> io::Result<()> {
+    fs::create_dir_all(&dir)?;
+    let logs = Logs {
+        backend: Mutex::new(RotatingFile::open(dir.join(LogFile::Backend.file_name()))?),
+        shell: Mutex::new(RotatingFile::open(dir.join(LogFile::Shell.file_name()))?),
+        dir,
+    };
+    let _ = LOGS.set(logs);
+    Ok(())
+}
+
+pub fn dir() -> Option<&'static Path> {
+    LOGS.get().map(|logs| logs.dir.as_path())
+}
+
+/// Record a line printed by the backend sidecar.
+pub fn backend(record: &LogRecord) {
+    if let Some(logs) = LOGS.get() {
+        write(&logs.backend, record);
+    }
+}
+
+/// Print a shell message to the console and record it. Use `log_info!` /
+/// `log_error!` rather than calling this directly.
+pub fn shell(stream: Stream, message: String) {
+    match stream {
+        Stream::Stdout => println!("{}", message),
+        Stream::Stderr => eprintln!("{}", message),
+    }
+    if let Some(logs) = LOGS.get() {
+        write(&logs.shell, &LogRecord::now(stream, std::process::id(), message));
+    }
+}
+
+fn write(file: &Mutex<RotatingFile>, record: &LogRecord) {
+    let Ok(line) = serde_json::to_string(record) else {
+        return;
+    };
+    // Nowhere left to report a failing log file but the console
+    if let Err(e) = file.lock().unwrap().write_line(&line) {
+        eprintln!("[Shell] Could not write log file: {}", e);
+    }
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        prune(&path);
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > MAX_FILE_SIZE {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for n in (1..MAX_ROTATED).rev() {
+            let from = rotated(&self.path, n);
+            if from.exists() {
+                fs::rename(&from, rotated(&self.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(&self.path, 1))?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        prune(&self.path);
+        Ok(())
+    }
+}
+
+fn rotated(path: &Path, n: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Delete rotated files past the count or age limit.
+fn prune(path: &Path) {
+    let now = SystemTime::now();
+    for n in 1..=MAX_ROTATED + 1 {
+        let file = rotated(path, n);
+        let expired = n > MAX_ROTATED
+            || fs::metadata(&file)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > MAX_AGE);
+        if expired {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+/// Open `path` in the platform's file manager.
+pub fn reveal(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let program = "xdg-open";
+
+    std::process::Command::new(program).arg(path).spawn()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_log_dir() -> Result<(), String> {
+    let dir = dir().ok_or("Log files are not available")?;
+    reveal(dir).map_err(|e| format!("Could not open {}: {}", dir.display(), e))
+}
+
+/// The last `lines` records of a log file, oldest first, reaching back into
+/// the most recent rotated file if the live one is shorter.
+#[tauri::command]
+pub fn tail_log(file: LogFile, lines: usize) -> Result<Vec<LogRecord>, String> {
+    let dir = dir().ok_or("Log files are not available")?;
+    let lines = lines.min(MAX_TAIL_LINES);
+    let path = dir.join(file.file_name());
+
+    let mut records = read_records(&path)?;
+    if records.len() < lines {
+        let mut older = read_records(&rotated(&path, 1))?;
+        older.append(&mut records);
+        records = older;
+    }
+    let skip = records.len().saturating_sub(lines);
+    Ok(records.split_off(skip))
+}
+
+fn read_records(path: &Path) -> Result<Vec<LogRecord>, String> {
+    match fs::read_to_string(path) {
+        // Skip lines cut short by a crash rather than failing the whole tail
+        Ok(text) => Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nlc-logs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rotates_once_the_file_is_full() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("backend.log");
+        let mut file = RotatingFile::open(path.clone()).unwrap();
+
+        let big = "x".repeat(MAX_FILE_SIZE as usize - 10);
+        file.write_line(&big).unwrap();
+        file.write_line("first").unwrap();
+        assert!(!rotated(&path, 1).exists());
+
+        file.write_line("second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        let older = fs::read_to_string(rotated(&path, 1)).unwrap();
+        assert_eq!(older.len(), big.len() + 1 + "first\n".len());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn shifts_rotated_files_and_drops_the_oldest() {
+        let dir = temp_dir("shift");
+        let path = dir.join("shell.log");
+        for n in 1..=MAX_ROTATED {
+            fs::write(rotated(&path, n), format!("{}\n", n)).unwrap();
+        }
+        let mut file = RotatingFile::open(path.clone()).unwrap();
+        file.write_line("live").unwrap();
+        file.rotate().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        assert_eq!(fs::read_to_string(rotated(&path, 1)).unwrap(), "live\n");
+        for n in 2..=MAX_ROTATED {
+            let expected = format!("{}\n", n - 1);
+            assert_eq!(fs::read_to_string(rotated(&path, n)).unwrap(), expected);
+        }
+        assert!(!rotated(&path, MAX_ROTATED + 1).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn prunes_rotated_files_past_the_age_limit() {
+        let dir = temp_dir("prune");
+        let path = dir.join("backend.log");
+        fs::write(rotated(&path, 1), "recent\n").unwrap();
+        fs::write(rotated(&path, 2), "old\n").unwrap();
+        let old = SystemTime::now() - MAX_AGE - Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(rotated(&path, 2))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        prune(&path);
+        assert!(rotated(&path, 1).exists());
+        assert!(!rotated(&path, 2).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tails_skip_lines_cut_short() {
+        let dir = temp_dir("read");
+        let path = dir.join("backend.log");
+        let record = LogRecord::now(Stream::Stdout, 1, "ready".into());
+        let line = serde_json::to_string(&record).unwrap();
+        fs::write(&path, format!("{}\n{}\n", line, &line[..line.len() / 2])).unwrap();
+
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "ready");
+        assert!(read_records(&dir.join("missing.log")).unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+//! Live backend output for the Debug window.
+//!
+//! Every sidecar line is parsed (the sidecar runs with `LOG_FORMAT=json`,
+//! see `src/monitoring/logging.py`) and kept in a ring buffer, so the window
+//! can show recent history when it opens. Lines are only emitted as
+//! `backend://log` events while a window has the stream switched on.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::logs::Stream;
+
+pub const EVENT_LINE: &str = "backend://log";
+
+const BUFFER_LINES: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Python `logging` level names.
+    fn from_python(name: &str) -> Self {
+        match name {
+            "DEBUG" => Level::Debug,
+            "WARNING" => Level::Warn,
+            "ERROR" | "CRITICAL" => Level::Error,
+            _ => Level::Info,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct BackendLine {
+    /// Increases by one per line, so the UI can drop duplicates after a resync.
+    pub seq: u64,
+    pub timestamp: String,
+    pub stream: Stream,
+    pub pid: u32,
+    pub level: Level,
+    /// Python logger name, for JSON lines.
+    pub logger: Option<String>,
+    pub message: String,
+    /// Remaining JSON fields (`location`, `exception`, `context`, extras).
+    pub data: Option<Map<String, Value>>,
+}
+
+pub struct LogStream {
+    lines: VecDeque<BackendLine>,
+    next_seq: u64,
+    streaming: bool,
+}
+
+impl LogStream {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::with_capacity(BUFFER_LINES),
+            next_seq: 0,
+            streaming: false,
+        }
+    }
+}
+
+/// Parse and buffer a sidecar line, emitting it if the stream is on.
+pub fn push(app: &AppHandle, stream: Stream, pid: u32, raw: &str) -> BackendLine {
+    let (level, logger, message, data) = parse(stream, raw);
+
+    let state = app.state::<Mutex<LogStream>>();
+    let mut state = state.lock().unwrap();
+    let line = BackendLine {
+        seq: state.next_seq,
+        timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        stream,
+        pid,
+        level,
+        logger,
+        message,
+        data,
+    };
+    state.next_seq += 1;
+    if state.lines.len() == BUFFER_LINES {
+        state.lines.pop_front();
+    }
+    state.lines.push_back(line.clone());
+    let streaming = state.streaming;
+    drop(state);
+
+    if streaming {
+        let _ = app.emit_all(EVENT_LINE, &line);
+    }
+    line
+}
+
+type Parsed = (Level, Option<String>, String, Option<Map<String, Value>>);
+
+fn parse(stream: Stream, raw: &str) -> Parsed {
+    if raw.starts_with('{') {
+        if let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(raw) {
+            if let Some(Value::String(message)) = fields.remove("message") {
+                let level = match fields.remove("level") {
+                    Some(Value::String(name)) => Level::from_python(&name),
+                    _ => Level::Info,
+                };
+                let logger = match fields.remove("logger") {
+                    Some(Value::String(name)) => Some(name),
+                    _ => None,
+                };
+                // The shell stamps its own time; the record's is redundant
+                fields.remove("timestamp");
+                let data = (!fields.is_empty()).then_some(fields);
+                return (level, logger, message, data);
+            }
+        }
+    }
+
+    // print()s, tracebacks and anything logged before logging is configured
+    let level = if raw.starts_with("Traceback") {
+        Level::Error
+    } else if stream == Stream::Stderr {
+        Level::Warn
+    } else {
+        Level::Info
+    };
+    (level, None, raw.to_string(), None)
+}
+
+/// Buffered lines, oldest first; the last `limit` if given.
+#[tauri::command]
+pub fn recent_backend_logs(
+    state: tauri::State<'_, Mutex<LogStream>>,
+    limit: Option<usize>,
+) -> Vec<BackendLine> {
+    let state = state.lock().unwrap();
+    let skip = limit.map_or(0, |limit| state.lines.len().saturating_sub(limit));
+    state.lines.iter().skip(skip).cloned().collect()
+}
+
+/// Start or stop emitting `backend://log` events (buffering continues).
+#[tauri::command]
+pub fn set_backend_log_stream(state: tauri::State<'_, Mutex<LogStream>>, enabled: bool) {
+    state.lock().unwrap().streaming = enabled;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_records() {
+        let raw = r#"{"timestamp": "2026-01-01T00:00:00Z", "level": "WARNING", "logger": "natlangchain.api", "message": "Slow request", "context": {"path": "/chain"}}"#;
+        let (level, logger, message, data) = parse(Stream::Stdout, raw);
+        assert_eq!(level, Level::Warn);
+        assert_eq!(logger.as_deref(), Some("natlangchain.api"));
+        assert_eq!(message, "Slow request");
+        let data = data.unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data["context"]["path"], "/chain");
+    }
+
+    #[test]
+    fn maps_python_levels() {
+        for (name, level) in [
+            ("DEBUG", Level::Debug),
+            ("INFO", Level::Info),
+            ("WARNING", Level::Warn),
+            ("ERROR", Level::Error),
+            ("CRITICAL", Level::Error),
+        ] {
+            let raw = format!(r#"{{"level": "{}", "message": "m"}}"#, name);
+            assert_eq!(parse(Stream::Stdout, &raw).0, level, "{}", name);
+        }
+        let (level, logger, _, data) = parse(Stream::Stdout, r#"{"message": "m"}"#);
+        assert_eq!(level, Level::Info);
+        assert_eq!(logger, None);
+        assert!(data.is_none());
+    }
+
+    #[test]
+    fn keeps_other_lines_verbatim() {
+        let (level, logger, message, data) = parse(Stream::Stdout, "Loading model...");
+        assert_eq!(level, Level::Info);
+        assert_eq!(logger, None);
+        assert_eq!(message, "Loading model...");
+        assert!(data.is_none());
+
+        assert_eq!(parse(Stream::Stderr, "warnings.warn(...)").0, Level::Warn);
+        let traceback = "Traceback (most recent call last):";
+        assert_eq!(parse(Stream::Stdout, traceback).0, Level::Error);
+    }
+
+    #[test]
+    fn treats_json_without_a_message_as_plain_text() {
+        for raw in [r#"{"level": "ERROR"}"#, r#"{"message": 42}"#, "{not json"] {
+            let (level, _, message, data) = parse(Stream::Stdout, raw);
+            assert_eq!(level, Level::Info);
+            assert_eq!(message, raw);
+            assert!(data.is_none());
+        }
+    }
+}
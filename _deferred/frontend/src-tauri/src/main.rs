@@ -1,58 +1,208 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use tauri::api::process::{Command, CommandEvent};
+// First, so `log_info!` / `log_error!` are visible in the other modules
+#[macro_use]
+mod logs;
+
+mod auth;
+mod backend;
+mod chainfile;
+mod chainindex;
+mod compat;
+mod datadir;
+mod encryption;
+mod health;
+mod instance;
+mod keystore;
+mod logstream;
+mod mine;
+mod offline;
+mod options;
+mod outbox;
+mod port;
+mod proxy;
+mod pyjson;
+mod search;
+mod settings;
+mod shutdown;
+mod signatures;
+mod startup;
+mod supervisor;
+mod verify;
+mod workspace;
+
 use std::sync::Mutex;
 
-struct BackendState {
-    child: Option<tauri::api::process::CommandChild>,
-}
+use tauri::Manager;
+
+use backend::BackendStatus;
 
 fn main() {
+    let context = tauri::generate_context!();
+
+    // Hand this launch to an already running shell rather than start a
+    // second backend on the same data
+    let instance = match tauri::api::path::app_data_dir(context.config()) {
+        Some(dir) => match instance::acquire(&dir) {
+            Ok(Some(instance)) => Some(instance),
+            Ok(None) => {
+                println!("[Shell] NatLangChain is already running");
+                return;
+            }
+            Err(e) => {
+                eprintln!("[Shell] Single-instance check failed, continuing anyway: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
     tauri::Builder::default()
-        .manage(Mutex::new(BackendState { child: None }))
+        .manage(options::LaunchOptions::from_env())
+        .manage(backend::Backends::new())
+        .manage(Mutex::new(logstream::LogStream::new()))
+        .manage(proxy::Proxy::new())
+        .manage(keystore::Keystore::new())
+        .manage(mine::Miner::new())
+        .manage(offline::OfflineChain::new())
+        .manage(outbox::Outbox::new())
+        .manage(chainindex::ChainIndex::new())
+        .manage(search::SearchIndex::new())
+        .invoke_handler(tauri::generate_handler![
+            backend::backend_ready,
+            backend::backend_status,
+            backend::get_backend_url,
+            chainfile::open_chain_file,
+            chainindex::chain_index_status,
+            chainindex::list_indexed_blocks,
+            chainindex::query_indexed_entries,
+            chainindex::resync_chain_index,
+            chainindex::sync_chain_index,
+            compat::backend_compat,
+            datadir::data_dir_info,
+            datadir::pick_data_dir,
+            datadir::relocate_data_dir,
+            datadir::reset_data_dir,
+            datadir::reveal_data_dir,
+            encryption::decrypt_metadata,
+            keystore::delete_identity,
+            keystore::export_identity,
+            keystore::generate_identity,
+            keystore::import_identity,
+            keystore::list_identities,
+            keystore::lock_identity,
+            keystore::pick_identity_file,
+            keystore::sign_entry,
+            keystore::unlock_identity,
+            logs::open_log_dir,
+            logs::tail_log,
+            logstream::recent_backend_logs,
+            logstream::set_backend_log_stream,
+            mine::cancel_mining,
+            mine::mine_block,
+            offline::get_block,
+            offline::get_chain,
+            offline::get_entries_by_author,
+            offline::get_entries_by_intent,
+            offline::get_latest_block,
+            offline::get_stats,
+            outbox::discard_outbox_entry,
+            outbox::list_outbox,
+            outbox::retry_outbox_entry,
+            outbox::submit_entry,
+            outbox::update_outbox_entry,
+            proxy::api_request,
+            search::search_entries_local,
+            settings::get_backend_settings,
+            settings::reset_backend_settings,
+            settings::update_backend_settings,
+            shutdown::quit_app,
+            shutdown::relaunch_app,
+            signatures::verify_chain_signatures,
+            signatures::verify_entry_signature,
+            startup::connect_external_backend,
+            verify::pick_chain_file,
+            verify::verify_chain,
+            workspace::create_workspace,
+            workspace::delete_workspace,
+            workspace::list_workspaces,
+            workspace::rename_workspace,
+            workspace::switch_workspace
+        ])
         .setup(|app| {
-            // Spawn the backend sidecar
-            let (mut rx, child) = Command::new_sidecar("natlangchain-backend")
-                .expect("failed to create sidecar command")
-                .spawn()
-                .expect("failed to spawn backend sidecar");
-
-            // Store the child process handle
-            let state = app.state::<Mutex<BackendState>>();
-            state.lock().unwrap().child = Some(child);
-
-            // Handle backend output in a separate thread
-            tauri::async_runtime::spawn(async move {
-                while let Some(event) = rx.recv().await {
-                    match event {
-                        CommandEvent::Stdout(line) => {
-                            println!("[Backend] {}", line);
-                        }
-                        CommandEvent::Stderr(line) => {
-                            eprintln!("[Backend Error] {}", line);
-                        }
-                        CommandEvent::Terminated(payload) => {
-                            println!("[Backend] Process terminated with code: {:?}", payload.code);
-                            break;
-                        }
-                        _ => {}
+            // Before anything else logs, so release builds keep a record
+            match app.path_resolver().app_log_dir() {
+                Some(dir) => {
+                    if let Err(e) = logs::init(dir) {
+                        eprintln!("[Shell] Could not open log files: {}", e);
                     }
                 }
-            });
+                None => eprintln!("[Shell] No log directory on this platform"),
+            }
+
+            if let Some(instance) = instance {
+                instance.listen(app.handle());
+            }
+
+            // Before the first spawn, which reads them; settings and data
+            // folder belong to the active workspace
+            let launch = app.state::<options::LaunchOptions>().inner().clone();
+            let workspaces = workspace::Registry::load(&app.handle(), &launch);
+            let awaiting_choice = workspaces.awaiting_choice;
+            app.manage(Mutex::new(workspaces));
+            let backend_settings = settings::load(&app.handle());
+            app.manage(Mutex::new(backend_settings));
+            let data_dir = datadir::DataDir::load(&app.handle());
+            app.manage(data_dir);
+
+            // Spawn the backend sidecar (the supervisor restarts it if it dies),
+            // or connect to the external backend given on the command line.
+            // Failures end up in a dialog rather than a panic, which release
+            // builds would turn into a silent abort. With several workspaces,
+            // the splash screen's picker starts it instead
+            if !awaiting_choice {
+                if let Err(e) = backend::start(&app.handle()) {
+                    health::fail(&app.handle(), e);
+                }
+            }
+
+            // Sends queued entries whenever the backend is ready
+            outbox::start(&app.handle());
+            // Keeps the local chain index in step with the backend
+            chainindex::start(&app.handle());
+
+            // Ctrl+C in dev, SIGTERM/SIGHUP at session end: drain the backend first
+            let handle = app.handle();
+            if let Err(e) = ctrlc::set_handler(move || shutdown::exit(&handle, 0)) {
+                log_error!("[Shell] Could not install termination handler: {}", e);
+            }
 
             Ok(())
         })
         .on_window_event(|event| {
             if let tauri::WindowEvent::Destroyed = event.event() {
-                // Kill the backend when the window is closed
-                if let Some(state) = event.window().try_state::<Mutex<BackendState>>() {
-                    if let Some(child) = state.lock().unwrap().child.take() {
-                        let _ = child.kill();
+                let window = event.window();
+                // Closing the main window exits the app, which stops the backend (see below)
+                if window.label() == health::SPLASH_WINDOW {
+                    // Closing the splash before the backend is ready means giving up
+                    let ready = backend::state(&window.app_handle()).lock().unwrap().status
+                        == BackendStatus::Ready;
+                    if !ready {
+                        if let Some(main) = window.get_window(health::MAIN_WINDOW) {
+                            let _ = main.close();
+                        }
                     }
                 }
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running NatLangChain");
+        .build(context)
+        .expect("error while building NatLangChain")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Let the backend drain requests and save the chain before
+                // Tauri kills remaining child processes
+                shutdown::shutdown_backend(app);
+            }
+        });
 }
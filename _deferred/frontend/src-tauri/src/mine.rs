@@ -0,0 +1,353 @@
+//! Proof of work in the shell.
+//!
+//! `NatLangChain.mine_pending_entries` tries one nonce after another on the
+//! backend's request thread until the block's hash starts with `difficulty`
+//! zeros. `mine_block` runs the same search on every core, reports progress
+//! as `mine://progress` events, and stops when cancelled or after a timeout.
+//! Hashes are computed exactly as `verify` recomputes them, so the nonce it
+//! finds is one `validate_chain` accepts. The UI gets the candidate from the
+//! backend's `GET /mine/template` and hands the result to `POST /mine/submit`.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::{pyjson, verify};
+
+pub const EVENT_PROGRESS: &str = "mine://progress";
+
+/// `mine_pending_entries`' default.
+pub const DEFAULT_DIFFICULTY: usize = 2;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+/// A SHA-256 hash has 64 hex digits.
+const MAX_DIFFICULTY: usize = 64;
+/// Nonces a thread claims at a time.
+const BATCH: u64 = 4096;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The search in progress, if any, so it can be cancelled.
+#[derive(Default)]
+pub struct Miner {
+    cancel: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl Miner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Progress {
+    pub hashes: u64,
+    pub hashes_per_second: f64,
+    /// The highest nonce handed out so far.
+    pub nonce: u64,
+    pub elapsed_ms: u64,
+}
+
+#[derive(serde::Serialize)]
+pub struct Mined {
+    /// The candidate with `nonce` and `hash` filled in.
+    pub block: Value,
+    pub nonce: u64,
+    pub hash: String,
+    pub difficulty: usize,
+    pub hashes: u64,
+    pub elapsed_ms: u64,
+}
+
+enum Outcome {
+    Found(u64, [u8; 32]),
+    Cancelled,
+    TimedOut,
+}
+
+/// The block's serialisation split around its nonce, with everything before
+/// the nonce already hashed; each try only hashes the nonce and the rest.
+struct Template {
+    prefix: Sha256,
+    suffix: Vec<u8>,
+}
+
+impl Template {
+    fn new(block: &serde_json::Map<String, Value>) -> Result<Self, String> {
+        let mut data = verify::hash_input(block).map_err(|e| format!("Can't mine: {}.", e))?;
+        data["nonce"] = json!(0);
+        let text = pyjson::dumps(&data, pyjson::DEFAULT);
+        // Keys are sorted, so only previous_hash and timestamp follow the
+        // block's own nonce; an entry's metadata may have a "nonce" too
+        let marker = "\"nonce\": 0";
+        let at = text.rfind(marker).ok_or("Can't mine: no nonce in the block")? + marker.len();
+        let mut prefix = Sha256::new();
+        prefix.update(&text.as_bytes()[..at - 1]);
+        Ok(Self {
+            prefix,
+            suffix: text.as_bytes()[at..].to_vec(),
+        })
+    }
+
+    fn hash(&self, nonce: u64) -> [u8; 32] {
+        let mut digits = [0u8; 20];
+        let mut start = digits.len();
+        let mut rest = nonce;
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (rest % 10) as u8;
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+        let mut hasher = self.prefix.clone();
+        hasher.update(&digits[start..]);
+        hasher.update(&self.suffix);
+        hasher.finalize().into()
+    }
+}
+
+/// Whether the hex form of `digest` starts with `difficulty` zeros.
+fn meets(digest: &[u8; 32], difficulty: usize) -> bool {
+    (0..difficulty).all(|i| {
+        let byte = digest[i / 2];
+        let nibble = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+        nibble == 0
+    })
+}
+
+fn hex(digest: &[u8; 32]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn search(
+    template: &Template,
+    difficulty: usize,
+    start: u64,
+    cancel: &AtomicBool,
+    timeout: Duration,
+    mut report: impl FnMut(Progress),
+) -> (Outcome, u64, Duration) {
+    let next = AtomicU64::new(start);
+    let hashes = AtomicU64::new(0);
+    let done = AtomicBool::new(false);
+    let found = Mutex::new(None::<(u64, [u8; 32])>);
+    let began = Instant::now();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+    let stopped = thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let first = next.fetch_add(BATCH, Ordering::Relaxed);
+                    let mut tried = 0;
+                    for nonce in first..first.saturating_add(BATCH) {
+                        tried += 1;
+                        let digest = template.hash(nonce);
+                        if meets(&digest, difficulty) {
+                            let mut found = found.lock().unwrap();
+                            // Threads can succeed together; keep the lowest nonce
+                            match *found {
+                                Some((lowest, _)) if lowest < nonce => {}
+                                _ => *found = Some((nonce, digest)),
+                            }
+                            done.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                    }
+                    hashes.fetch_add(tried, Ordering::Relaxed);
+                }
+            });
+        }
+
+        // This thread watches for cancellation and the timeout, and reports
+        let mut last_report = Instant::now();
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if done.load(Ordering::Relaxed) {
+                return None;
+            }
+            let stop = if cancel.load(Ordering::Relaxed) {
+                Some(Outcome::Cancelled)
+            } else if began.elapsed() >= timeout {
+                Some(Outcome::TimedOut)
+            } else {
+                None
+            };
+            if stop.is_some() {
+                done.store(true, Ordering::Relaxed);
+                return stop;
+            }
+            if last_report.elapsed() >= PROGRESS_INTERVAL {
+                last_report = Instant::now();
+                let elapsed = began.elapsed();
+                let hashes = hashes.load(Ordering::Relaxed);
+                report(Progress {
+                    hashes,
+                    hashes_per_second: hashes as f64 / elapsed.as_secs_f64(),
+                    nonce: next.load(Ordering::Relaxed),
+                    elapsed_ms: elapsed.as_millis() as u64,
+                });
+            }
+        }
+    });
+
+    // A nonce found just as the search was stopped still counts
+    let outcome = match found.into_inner().unwrap() {
+        Some((nonce, digest)) => Outcome::Found(nonce, digest),
+        None => stopped.unwrap_or(Outcome::Cancelled),
+    };
+    (outcome, hashes.into_inner(), began.elapsed())
+}
+
+/// Find a nonce for `block` (`index`, `timestamp`, `entries`, `previous_hash`
+/// and optionally the `nonce` to start from, as `Block.to_dict` has them)
+/// whose hash starts with `difficulty` zeros. Gives up after `timeout_secs`
+/// (five minutes by default) or when `cancel_mining` is called.
+#[tauri::command]
+pub async fn mine_block(
+    app: AppHandle,
+    block: Value,
+    difficulty: Option<usize>,
+    timeout_secs: Option<u64>,
+) -> Result<Mined, String> {
+    let difficulty = difficulty.unwrap_or(DEFAULT_DIFFICULTY);
+    if difficulty > MAX_DIFFICULTY {
+        return Err(format!("The difficulty can be at most {}.", MAX_DIFFICULTY));
+    }
+    let Value::Object(mut block) = block else {
+        return Err("The block is not a JSON object.".into());
+    };
+    let template = Template::new(&block)?;
+    let start = block.get("nonce").and_then(Value::as_u64).unwrap_or(0);
+    let timeout = timeout_secs.map_or(DEFAULT_TIMEOUT, Duration::from_secs);
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let miner = app.state::<Miner>();
+        let mut current = miner.cancel.lock().unwrap();
+        if current.is_some() {
+            return Err("A block is already being mined.".into());
+        }
+        *current = Some(cancel.clone());
+    }
+
+    let events = app.clone();
+    let searched = tauri::async_runtime::spawn_blocking(move || {
+        search(&template, difficulty, start, &cancel, timeout, |progress| {
+            let _ = events.emit_all(EVENT_PROGRESS, progress);
+        })
+    })
+    .await;
+    app.state::<Miner>().cancel.lock().unwrap().take();
+    let (outcome, hashes, elapsed) = searched.map_err(|e| e.to_string())?;
+
+    match outcome {
+        Outcome::Found(nonce, digest) => {
+            let hash = hex(&digest);
+            log_info!(
+                "[Shell] Mined nonce {} at difficulty {} ({} hashes in {:.1}s)",
+                nonce,
+                difficulty,
+                hashes,
+                elapsed.as_secs_f64()
+            );
+            block.insert("nonce".into(), json!(nonce));
+            block.insert("hash".into(), json!(hash));
+            Ok(Mined {
+                block: Value::Object(block),
+                nonce,
+                hash,
+                difficulty,
+                hashes,
+                elapsed_ms: elapsed.as_millis() as u64,
+            })
+        }
+        Outcome::Cancelled => Err("Mining was cancelled.".into()),
+        Outcome::TimedOut => Err(format!(
+            "No nonce found within {} seconds ({} hashes tried).",
+            timeout.as_secs(),
+            hashes
+        )),
+    }
+}
+
+/// Stop the search in progress. Resolves to whether there was one.
+#[tauri::command]
+pub fn cancel_mining(miner: tauri::State<'_, Miner>) -> bool {
+    match miner.cancel.lock().unwrap().as_ref() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hashed and mined by the backend's `Block`; see tests/fixtures/generate.py
+    const CHAIN: &str = include_str!("../tests/fixtures/chain.json");
+
+    fn blocks() -> Vec<Value> {
+        let mut chain: Value = serde_json::from_str(CHAIN).unwrap();
+        serde_json::from_value(chain["chain"].take()).unwrap()
+    }
+
+    fn mine(block: &Value, difficulty: usize) -> Value {
+        let block = block.as_object().unwrap();
+        let template = Template::new(block).unwrap();
+        let cancel = AtomicBool::new(false);
+        let (outcome, _, _) = search(&template, difficulty, 0, &cancel, DEFAULT_TIMEOUT, |_| {});
+        let Outcome::Found(nonce, digest) = outcome else {
+            panic!("no nonce found");
+        };
+        let mut block = block.clone();
+        block.insert("nonce".into(), json!(nonce));
+        block.insert("hash".into(), json!(hex(&digest)));
+        Value::Object(block)
+    }
+
+    #[test]
+    fn mines_a_block_the_backend_accepts() {
+        let mut blocks = blocks();
+        // A different timestamp than the fixture's, so the nonce is new
+        blocks[1]["timestamp"] = json!(1700000123.25);
+        blocks[1]["nonce"] = json!(0);
+        blocks[1] = mine(&blocks[1], 2);
+        assert!(blocks[1]["hash"].as_str().unwrap().starts_with("00"));
+
+        let report = verify::verify(&blocks, 2);
+        let problems: Vec<_> = report.blocks.iter().map(|block| &block.problems).collect();
+        assert!(report.valid, "{:?}", problems);
+        assert_eq!(report.blocks[1].proof_of_work, verify::Check::Passed);
+    }
+
+    #[test]
+    fn ignores_a_nonce_in_entry_metadata() {
+        let mut blocks = blocks();
+        blocks[1]["entries"][0]["metadata"] = json!({ "nonce": 0 });
+        blocks[1] = mine(&blocks[1], 2);
+        assert!(verify::verify(&blocks, 2).valid);
+    }
+
+    #[test]
+    fn meets_counts_hex_digits() {
+        let mut digest = [0xffu8; 32];
+        assert!(meets(&digest, 0));
+        assert!(!meets(&digest, 1));
+        digest[0] = 0x0f;
+        assert!(meets(&digest, 1));
+        assert!(!meets(&digest, 2));
+        digest[0] = 0;
+        assert!(meets(&digest, 2));
+        assert!(!meets(&digest, 3));
+    }
+}
@@ -0,0 +1,203 @@
+//! Read-only answers from the saved chain while the backend is down.
+//!
+//! When the sidecar isn't running, the explorer and dashboard fall back to
+//! these commands. They answer `GET /chain`, `/block/<i>`, `/block/latest`,
+//! `/stats` and the author and intent lookups with the JSON the backend
+//! would send, plus `"offline": true`, from the current workspace's chain
+//! file. The file is read as `JSONFileStorage` writes it (see `chainfile`)
+//! and kept in memory until it changes on disk.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::{chainfile, encryption, verify};
+
+/// The last chain read.
+#[derive(Default)]
+pub struct OfflineChain {
+    cached: Mutex<Option<Cached>>,
+}
+
+impl OfflineChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Clone)]
+struct Cached {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    chain: Arc<Loaded>,
+}
+
+pub(crate) struct Loaded {
+    /// `NatLangChain.to_dict()`, as `/chain` returns it under `"chain"`.
+    document: Value,
+    valid: bool,
+}
+
+impl Loaded {
+    pub(crate) fn blocks(&self) -> &[Value] {
+        self.document["chain"].as_array().map_or(&[], Vec::as_slice)
+    }
+
+    fn pending_count(&self) -> usize {
+        self.document["pending_entries"].as_array().map_or(0, Vec::len)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.blocks().iter().flat_map(|block| {
+            let entries = block["entries"].as_array().map_or(&[][..], Vec::as_slice);
+            entries.iter().map(move |entry| (block, entry))
+        })
+    }
+}
+
+fn load(path: &Path, key: Option<&str>) -> Result<Loaded, String> {
+    let document = match chainfile::read(path, key).map_err(|e| e.to_string())? {
+        // An export of just the blocks
+        Value::Array(blocks) => json!({ "chain": blocks, "pending_entries": [] }),
+        document => document,
+    };
+    let blocks = chainfile::blocks(document.clone()).map_err(|e| e.to_string())?;
+    let valid = verify::verify(&blocks, verify::DEFAULT_DIFFICULTY).valid;
+    Ok(Loaded { document, valid })
+}
+
+/// The current workspace's chain, read again only if the file changed.
+pub(crate) async fn current(app: &AppHandle) -> Result<Arc<Loaded>, String> {
+    let path = chainfile::locate(app, None)?;
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+    let cached = app.state::<OfflineChain>().cached.lock().unwrap().clone();
+    if let Some(cached) = cached.filter(|c| c.path == path && c.modified == modified) {
+        return Ok(cached.chain);
+    }
+    let key = encryption::key_or_default(app, None);
+    let file = path.clone();
+    // Decrypting and checking a long chain takes a while
+    let loaded = tauri::async_runtime::spawn_blocking(move || load(&file, key.as_deref()))
+        .await
+        .map_err(|e| e.to_string())??;
+    let chain = Arc::new(loaded);
+    *app.state::<OfflineChain>().cached.lock().unwrap() = Some(Cached {
+        path,
+        modified,
+        chain: chain.clone(),
+    });
+    Ok(chain)
+}
+
+fn offline(mut response: Value) -> Value {
+    if let Value::Object(fields) = &mut response {
+        fields.insert("offline".into(), Value::Bool(true));
+    }
+    response
+}
+
+fn lookup<'a>(
+    chain: &'a Loaded,
+    matches: impl Fn(&Value) -> bool + 'a,
+) -> impl Iterator<Item = Value> + 'a {
+    chain.entries().filter(move |(_, entry)| matches(entry)).map(|(block, entry)| {
+        json!({
+            "block_index": block["index"],
+            "block_hash": block["hash"],
+            "entry": entry,
+        })
+    })
+}
+
+/// `GET /chain`
+#[tauri::command]
+pub async fn get_chain(app: AppHandle) -> Result<Value, String> {
+    let chain = current(&app).await?;
+    let response = json!({
+        "length": chain.blocks().len(),
+        "chain": chain.document,
+        "valid": chain.valid,
+    });
+    Ok(offline(response))
+}
+
+/// `GET /block/<index>`
+#[tauri::command]
+pub async fn get_block(app: AppHandle, index: usize) -> Result<Value, String> {
+    let chain = current(&app).await?;
+    let blocks = chain.blocks();
+    match blocks.get(index) {
+        Some(block) => Ok(offline(block.clone())),
+        None => Err(format!(
+            "Block not found (valid range 0-{})",
+            blocks.len().saturating_sub(1)
+        )),
+    }
+}
+
+/// `GET /block/latest`
+#[tauri::command]
+pub async fn get_latest_block(app: AppHandle) -> Result<Value, String> {
+    let chain = current(&app).await?;
+    let latest = chain.blocks().last().ok_or("No blocks in chain")?;
+    let response = json!({
+        "block": latest,
+        "chain_length": chain.blocks().len(),
+        "pending_entries": chain.pending_count(),
+    });
+    Ok(offline(response))
+}
+
+/// `GET /stats`. Every backend feature is unavailable while it is down.
+#[tauri::command]
+pub async fn get_stats(app: AppHandle) -> Result<Value, String> {
+    let chain = current(&app).await?;
+    let authors: HashSet<&str> = chain
+        .entries()
+        .filter_map(|(_, entry)| entry["author"].as_str())
+        .collect();
+    let response = json!({
+        "blocks": chain.blocks().len(),
+        "pending_entries": chain.pending_count(),
+        "total_entries": chain.entries().count(),
+        "unique_authors": authors.len(),
+        "chain_valid": chain.valid,
+        "features": {
+            "llm_validation": false,
+            "semantic_search": false,
+            "contract_management": false,
+            "identity_signing": false,
+            "module_manifests": 0,
+        },
+    });
+    Ok(offline(response))
+}
+
+/// `GET /entries/author/<author>`
+#[tauri::command]
+pub async fn get_entries_by_author(app: AppHandle, author: String) -> Result<Value, String> {
+    let chain = current(&app).await?;
+    let entries: Vec<Value> =
+        lookup(&chain, |entry| entry["author"].as_str() == Some(author.as_str())).collect();
+    let response = json!({ "author": author, "count": entries.len(), "entries": entries });
+    Ok(offline(response))
+}
+
+/// `NatLangChain.get_entries_by_intent`: entries whose intent contains
+/// `intent`, ignoring case.
+#[tauri::command]
+pub async fn get_entries_by_intent(app: AppHandle, intent: String) -> Result<Value, String> {
+    let chain = current(&app).await?;
+    let keyword = intent.to_lowercase();
+    let entries: Vec<Value> = lookup(&chain, |entry| {
+        entry["intent"].as_str().is_some_and(|i| i.to_lowercase().contains(&keyword))
+    })
+    .collect();
+    let response = json!({ "intent": intent, "count": entries.len(), "entries": entries });
+    Ok(offline(response))
+}
@@ -0,0 +1,157 @@
+//! Launch options from the command line and environment.
+//!
+//! `--backend-url <url>` (or `NATLANGCHAIN_BACKEND_URL`) points the shell at a
+//! backend that is already running, e.g. in Docker or under a debugger,
+//! instead of spawning the sidecar. Its API key comes from
+//! `NATLANGCHAIN_BACKEND_API_KEY` only, so it doesn't show up in process
+//! listings. `--sidecar-fallback` (or `NATLANGCHAIN_SIDECAR_FALLBACK=1`)
+//! spawns the sidecar after all if that backend can't be reached.
+//! `--workspace <id or name>` (or `NATLANGCHAIN_WORKSPACE`) opens that
+//! workspace without asking.
+
+use std::env;
+
+const ENV_BACKEND_URL: &str = "NATLANGCHAIN_BACKEND_URL";
+const ENV_BACKEND_API_KEY: &str = "NATLANGCHAIN_BACKEND_API_KEY";
+const ENV_SIDECAR_FALLBACK: &str = "NATLANGCHAIN_SIDECAR_FALLBACK";
+const ENV_WORKSPACE: &str = "NATLANGCHAIN_WORKSPACE";
+
+#[derive(Clone, Debug, Default)]
+pub struct LaunchOptions {
+    /// Base URL of an external backend, without a trailing slash.
+    pub backend_url: Option<String>,
+    pub backend_api_key: Option<String>,
+    pub sidecar_fallback: bool,
+    pub workspace: Option<String>,
+}
+
+impl LaunchOptions {
+    /// Command-line flags take precedence over environment variables.
+    pub fn from_env() -> Self {
+        Self::parse(|name| env::var(name).ok(), env::args().skip(1))
+    }
+
+    /// `from_env` over a given environment and arguments (without the
+    /// program name).
+    fn parse(var: impl Fn(&str) -> Option<String>, args: impl IntoIterator<Item = String>) -> Self {
+        let mut options = LaunchOptions {
+            backend_url: non_empty(var(ENV_BACKEND_URL)),
+            backend_api_key: non_empty(var(ENV_BACKEND_API_KEY)),
+            sidecar_fallback: var(ENV_SIDECAR_FALLBACK).is_some_and(|value| is_truthy(&value)),
+            workspace: non_empty(var(ENV_WORKSPACE)),
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.split_once('=') {
+                Some(("--backend-url", url)) => options.backend_url = non_empty(Some(url.into())),
+                None if arg == "--backend-url" => options.backend_url = non_empty(args.next()),
+                None if arg == "--sidecar-fallback" => options.sidecar_fallback = true,
+                Some(("--workspace", name)) => options.workspace = non_empty(Some(name.into())),
+                None if arg == "--workspace" => options.workspace = non_empty(args.next()),
+                _ => {}
+            }
+        }
+
+        options.backend_url = options
+            .backend_url
+            .map(|url| url.trim_end_matches('/').to_string());
+        options
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(vars: &[(&str, &str)], args: &[&str]) -> LaunchOptions {
+        let var = |name: &str| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        };
+        LaunchOptions::parse(var, args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn spawns_the_sidecar_by_default() {
+        let options = parse(&[], &[]);
+        assert_eq!(options.backend_url, None);
+        assert_eq!(options.backend_api_key, None);
+        assert!(!options.sidecar_fallback);
+        assert_eq!(options.workspace, None);
+    }
+
+    #[test]
+    fn reads_the_environment() {
+        let options = parse(
+            &[
+                (ENV_BACKEND_URL, "http://localhost:5000/"),
+                (ENV_BACKEND_API_KEY, "key"),
+                (ENV_SIDECAR_FALLBACK, " Yes "),
+                (ENV_WORKSPACE, "Research"),
+            ],
+            &[],
+        );
+        assert_eq!(
+            options.backend_url.as_deref(),
+            Some("http://localhost:5000")
+        );
+        assert_eq!(options.backend_api_key.as_deref(), Some("key"));
+        assert!(options.sidecar_fallback);
+        assert_eq!(options.workspace.as_deref(), Some("Research"));
+    }
+
+    #[test]
+    fn ignores_empty_and_false_values() {
+        let options = parse(
+            &[
+                (ENV_BACKEND_URL, "  "),
+                (ENV_BACKEND_API_KEY, ""),
+                (ENV_SIDECAR_FALLBACK, "0"),
+            ],
+            &[],
+        );
+        assert_eq!(options.backend_url, None);
+        assert_eq!(options.backend_api_key, None);
+        assert!(!options.sidecar_fallback);
+    }
+
+    #[test]
+    fn flags_take_precedence_over_the_environment() {
+        let vars = [(ENV_BACKEND_URL, "http://env:5000"), (ENV_WORKSPACE, "env")];
+        let separate = parse(
+            &vars,
+            &["--backend-url", "http://flag:5000/", "--workspace", "flag"],
+        );
+        assert_eq!(separate.backend_url.as_deref(), Some("http://flag:5000"));
+        assert_eq!(separate.workspace.as_deref(), Some("flag"));
+
+        let joined = parse(
+            &vars,
+            &[
+                "--backend-url=http://flag:5000",
+                "--workspace=flag",
+                "--sidecar-fallback",
+            ],
+        );
+        assert_eq!(joined.backend_url.as_deref(), Some("http://flag:5000"));
+        assert_eq!(joined.workspace.as_deref(), Some("flag"));
+        assert!(joined.sidecar_fallback);
+    }
+
+    #[test]
+    fn ignores_unknown_arguments_and_missing_values() {
+        let options = parse(&[], &["--verbose", "file.txt", "--backend-url"]);
+        assert_eq!(options.backend_url, None);
+        assert!(!options.sidecar_fallback);
+    }
+}
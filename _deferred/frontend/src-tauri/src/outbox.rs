@@ -0,0 +1,684 @@
+//! Entries waiting to reach the backend.
+//!
+//! In the desktop app the entry form submits through `submit_entry` rather
+//! than posting to `/entry` itself, so an entry typed while the sidecar is
+//! restarting or stopped isn't lost. The entry, with its local signature if
+//! it has one, is first written to `outbox.json` in the workspace's app data
+//! folder and then sent. If the backend can't be reached it stays queued and
+//! is retried with exponential backoff whenever the backend is ready.
+//!
+//! Rejections are kept with the backend's reason. A rate limit is retried
+//! once the backend's `retry_after` has passed; a duplicate, a failed quality
+//! check, a bad timestamp or any other rejection waits until the entry is
+//! edited, retried or discarded.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use rand::RngCore;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::backend::{self, BackendStatus};
+use crate::proxy::Proxy;
+use crate::{auth, keystore, settings, workspace};
+
+const OUTBOX_FILE: &str = "outbox.json";
+
+pub const EVENT_CHANGED: &str = "outbox://changed";
+
+/// How often the queue is checked for entries that are due.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Wait after the first failed attempt; doubles with every further one.
+const FIRST_BACKOFF_SECS: i64 = 5;
+const MAX_BACKOFF_SECS: i64 = 600;
+/// For a rate limit that doesn't say how long to wait.
+const DEFAULT_RETRY_AFTER_SECS: u64 = 60;
+/// The backend rejects timestamps more than five minutes old by default
+/// (`max_timestamp_drift`), so older signatures are renewed before sending.
+const RESIGN_AFTER_SECS: i64 = 240;
+
+/// Metadata fields `sign_entry_dict` adds.
+const SIGNATURE_FIELDS: [&str; 3] = ["signature", "public_key", "signer_fingerprint"];
+
+/// Entries being sent right now, which can't be edited or sent again.
+#[derive(Default)]
+pub struct Outbox {
+    sending: Mutex<HashSet<String>>,
+    /// Held while `outbox.json` is read, changed and written back.
+    file: Mutex<()>,
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct QueuedEntry {
+    pub id: String,
+    /// The `POST /entry` body: content, author, intent, metadata and, for a
+    /// signed entry, the timestamp its signature covers.
+    pub entry: Value,
+    /// The shell identity that signed the entry, if any.
+    pub signed_with: Option<String>,
+    pub queued_at: String,
+    pub status: QueueStatus,
+    pub attempts: u32,
+    /// When a queued entry is next sent (RFC 3339).
+    pub next_attempt: String,
+    /// Why the last attempt didn't get an answer from the backend.
+    pub last_error: Option<String>,
+    /// The backend's answer to the last attempt, if it turned the entry down.
+    pub rejection: Option<Rejection>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueStatus {
+    /// Sent once `next_attempt` has passed and the backend is ready.
+    Queued,
+    /// Rejected by the backend; waits for the user.
+    Held,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Rejection {
+    pub kind: RejectionKind,
+    /// HTTP status of the response.
+    pub status: u16,
+    /// The backend's `reason`, e.g. `quality_check_failed`.
+    pub reason: Option<String>,
+    pub message: String,
+    /// Seconds to wait, for a rate limit.
+    pub retry_after: Option<u64>,
+    /// The whole response, which has e.g. the quality issues.
+    pub details: Value,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionKind {
+    RateLimit,
+    Duplicate,
+    Quality,
+    Timestamp,
+    /// Anything else, e.g. forbidden metadata or a signature that doesn't verify.
+    Invalid,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct OutboxFile {
+    entries: Vec<QueuedEntry>,
+}
+
+/// What became of a submission.
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Submission {
+    /// Accepted; `response` is what `POST /entry` returned.
+    Delivered { response: Value },
+    /// Still in the outbox, queued or held.
+    Queued { item: Box<QueuedEntry> },
+}
+
+enum Failure {
+    /// No answer, or one that says nothing about the entry (5xx, 401, ...).
+    Unanswered(String),
+    Rejected(Rejection),
+}
+
+fn outbox_path(app: &AppHandle) -> Option<PathBuf> {
+    workspace::data_root(app).map(|dir| dir.join(OUTBOX_FILE))
+}
+
+fn load(app: &AppHandle) -> Result<OutboxFile, String> {
+    let path = outbox_path(app).ok_or("No data folder on this platform")?;
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("{} is damaged: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(OutboxFile::default()),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+}
+
+fn save(app: &AppHandle, outbox: &OutboxFile) -> Result<(), String> {
+    let path = outbox_path(app).ok_or("No data folder on this platform")?;
+    let write = || -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write then rename, so a crash never loses the queue
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(outbox)?)?;
+        // Entries may hold sensitive metadata that isn't encrypted yet
+        settings::restrict_permissions(&temp)?;
+        fs::rename(&temp, &path)
+    };
+    write().map_err(|e| format!("Could not save {}: {}", path.display(), e))
+}
+
+/// Load the outbox, let `change` modify it, and save it if that succeeds.
+fn modify<T>(
+    app: &AppHandle,
+    change: impl FnOnce(&mut OutboxFile) -> Result<T, String>,
+) -> Result<T, String> {
+    let outbox = app.state::<Outbox>();
+    let _file = outbox.file.lock().unwrap();
+    let mut contents = load(app)?;
+    let result = change(&mut contents)?;
+    save(app, &contents)?;
+    Ok(result)
+}
+
+fn find<'a>(outbox: &'a mut OutboxFile, id: &str) -> Result<&'a mut QueuedEntry, String> {
+    outbox
+        .entries
+        .iter_mut()
+        .find(|item| item.id == id)
+        .ok_or_else(|| "The entry is no longer in the outbox.".to_string())
+}
+
+fn emit(app: &AppHandle) {
+    match load(app) {
+        Ok(outbox) => {
+            let _ = app.emit_all(EVENT_CHANGED, outbox.entries);
+        }
+        Err(e) => log_error!("[Shell] {}", e),
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn after_secs(secs: i64) -> String {
+    (Utc::now() + chrono::Duration::seconds(secs)).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn is_due(item: &QueuedEntry) -> bool {
+    let pending = DateTime::parse_from_rfc3339(&item.next_attempt)
+        .ok()
+        .filter(|at| at.with_timezone(&Utc) > Utc::now());
+    item.status == QueueStatus::Queued && pending.is_none()
+}
+
+fn backoff_secs(attempts: u32) -> i64 {
+    let doublings = attempts.saturating_sub(1).min(16);
+    (FIRST_BACKOFF_SECS << doublings).min(MAX_BACKOFF_SECS)
+}
+
+fn backend_ready(app: &AppHandle) -> bool {
+    backend::state(app).lock().unwrap().status == BackendStatus::Ready
+}
+
+/// The entry's content, author, intent and metadata, without a timestamp or
+/// signature that no longer fit it.
+fn unsigned(entry: Value) -> Result<Value, String> {
+    let Value::Object(mut fields) = entry else {
+        return Err("The entry is not a JSON object.".into());
+    };
+    for field in ["content", "author", "intent"] {
+        if !fields.get(field).is_some_and(Value::is_string) {
+            return Err(format!("The entry has no {}.", field));
+        }
+    }
+    fields.remove("timestamp");
+    if let Some(Value::Object(metadata)) = fields.get_mut("metadata") {
+        for field in SIGNATURE_FIELDS {
+            metadata.remove(field);
+        }
+    }
+    Ok(Value::Object(fields))
+}
+
+/// Whether a signed entry's timestamp is old enough to be rejected soon.
+fn signature_expiring(entry: &Value) -> bool {
+    entry["timestamp"]
+        .as_str()
+        .and_then(|ts| NaiveDateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S%.f").ok())
+        .is_some_and(|signed| {
+            Utc::now().naive_utc() - signed > chrono::Duration::seconds(RESIGN_AFTER_SECS)
+        })
+}
+
+fn rejection(status: u16, body: Value, retry_after: Option<u64>) -> Rejection {
+    let reason = body["reason"].as_str().map(str::to_string);
+    let message = body["message"]
+        .as_str()
+        .or(body["error"].as_str())
+        .unwrap_or("The backend rejected the entry.")
+        .to_string();
+    let kind = match (status, reason.as_deref()) {
+        (429, _) | (_, Some("rate_limit")) => RejectionKind::RateLimit,
+        (_, Some("duplicate")) => RejectionKind::Duplicate,
+        (_, Some("quality_check_failed" | "quality_needs_improvement")) => RejectionKind::Quality,
+        (_, Some("invalid_timestamp")) => RejectionKind::Timestamp,
+        _ => RejectionKind::Invalid,
+    };
+    let retry_after = match kind {
+        RejectionKind::RateLimit => Some(
+            retry_after
+                .or(body["retry_after"].as_u64())
+                .unwrap_or(DEFAULT_RETRY_AFTER_SECS),
+        ),
+        _ => None,
+    };
+    Rejection {
+        kind,
+        status,
+        reason,
+        message,
+        retry_after,
+        details: body,
+    }
+}
+
+/// `POST /entry` to the backend, the way `api_request` would. Resolves
+/// to the backend's response if the entry was accepted.
+async fn send(app: &AppHandle, entry: &Value) -> Result<Value, Failure> {
+    let (base_url, api_token) = {
+        let state = backend::state(app);
+        let state = state.lock().unwrap();
+        (state.base_url(), state.api_token.clone())
+    };
+    let client = app
+        .state::<Proxy>()
+        .client()
+        .map_err(|e| Failure::Unanswered(e.to_string()))?;
+    let mut request = client.post(format!("{}/entry", base_url)).json(entry);
+    if !api_token.is_empty() {
+        request = request.header(auth::HEADER, &api_token);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) if e.is_timeout() => {
+            return Err(Failure::Unanswered(
+                "The backend did not answer in time.".into(),
+            ))
+        }
+        Err(_) => return Err(Failure::Unanswered("The backend is unavailable.".into())),
+    };
+    let status = response.status().as_u16();
+    let retry_after = response
+        .headers()
+        .get("retry-after")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+
+    match status {
+        200..=299 => Ok(body),
+        400 | 422 | 429 => Err(Failure::Rejected(rejection(status, body, retry_after))),
+        _ => Err(Failure::Unanswered(match body["error"].as_str() {
+            Some(error) => format!("The backend answered {}: {}", status, error),
+            None => format!("The backend answered {}.", status),
+        })),
+    }
+}
+
+/// Send one entry and record the outcome in the outbox.
+async fn deliver(app: &AppHandle, id: &str) -> Result<Submission, String> {
+    let claimed = app
+        .state::<Outbox>()
+        .sending
+        .lock()
+        .unwrap()
+        .insert(id.to_string());
+    if !claimed {
+        return Err("The entry is already being sent.".into());
+    }
+    let result = attempt(app, id).await;
+    app.state::<Outbox>().sending.lock().unwrap().remove(id);
+    emit(app);
+    result
+}
+
+async fn attempt(app: &AppHandle, id: &str) -> Result<Submission, String> {
+    let item = modify(app, |outbox| {
+        let item = find(outbox, id)?;
+        if let Some(identity) = item.signed_with.clone() {
+            if signature_expiring(&item.entry) {
+                // Keep the old signature if the key is locked; the backend
+                // will say whether it's still acceptable
+                match keystore::sign_entry(app.clone(), identity, unsigned(item.entry.clone())?) {
+                    Ok(entry) => item.entry = entry,
+                    Err(e) => log_info!("[Shell] Outbox: can't renew signature of {}: {}", id, e),
+                }
+            }
+        }
+        Ok(item.clone())
+    })?;
+
+    let failure = match send(app, &item.entry).await {
+        Ok(response) => {
+            log_info!("[Shell] Outbox: delivered {}", id);
+            modify(app, |outbox| {
+                outbox.entries.retain(|item| item.id != id);
+                Ok(())
+            })?;
+            return Ok(Submission::Delivered { response });
+        }
+        Err(failure) => failure,
+    };
+
+    let item = modify(app, |outbox| {
+        let item = find(outbox, id)?;
+        item.attempts += 1;
+        match failure {
+            Failure::Unanswered(error) => {
+                log_info!("[Shell] Outbox: {} not sent: {}", id, error);
+                item.status = QueueStatus::Queued;
+                item.next_attempt = after_secs(backoff_secs(item.attempts));
+                item.last_error = Some(error);
+                item.rejection = None;
+            }
+            Failure::Rejected(rejection) => {
+                log_info!("[Shell] Outbox: {} rejected: {}", id, rejection.message);
+                match rejection.retry_after {
+                    Some(secs) => {
+                        item.status = QueueStatus::Queued;
+                        item.next_attempt = after_secs(secs as i64);
+                    }
+                    None => item.status = QueueStatus::Held,
+                }
+                item.last_error = None;
+                item.rejection = Some(rejection);
+            }
+        }
+        Ok(item.clone())
+    })?;
+    Ok(Submission::Queued {
+        item: Box::new(item),
+    })
+}
+
+/// Send due entries whenever the backend is ready, oldest first.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if !backend_ready(&app) {
+                continue;
+            }
+            let due: Vec<String> = match load(&app) {
+                Ok(outbox) => outbox
+                    .entries
+                    .iter()
+                    .filter(|item| is_due(item))
+                    .map(|item| item.id.clone())
+                    .collect(),
+                Err(e) => {
+                    log_error!("[Shell] {}", e);
+                    continue;
+                }
+            };
+            for id in due {
+                match deliver(&app, &id).await {
+                    // Unreachable again; wait for the next round
+                    Ok(Submission::Queued { item }) if item.last_error.is_some() => break,
+                    Ok(_) => {}
+                    Err(e) => log_info!("[Shell] Outbox: {} skipped: {}", id, e),
+                }
+            }
+        }
+    });
+}
+
+/// Queue `entry` (content, author, intent, metadata, and the timestamp a
+/// local signature covers) and send it right away if the backend is ready.
+/// `signed_with` names the identity that signed it, so the signature can be
+/// renewed. An entry the backend rejects for anything but a rate limit is
+/// dropped again and the rejection returned as the error, as a direct
+/// `POST /entry` would.
+#[tauri::command]
+pub async fn submit_entry(
+    app: AppHandle,
+    entry: Value,
+    signed_with: Option<String>,
+) -> Result<Submission, String> {
+    if !entry.is_object() {
+        return Err("The entry is not a JSON object.".into());
+    }
+    let mut id = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut id);
+    let id: String = id.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let item = QueuedEntry {
+        id: id.clone(),
+        entry,
+        signed_with,
+        queued_at: now(),
+        status: QueueStatus::Queued,
+        attempts: 0,
+        next_attempt: now(),
+        last_error: None,
+        rejection: None,
+    };
+    modify(&app, |outbox| {
+        outbox.entries.push(item.clone());
+        Ok(())
+    })?;
+
+    if !backend_ready(&app) {
+        log_info!("[Shell] Outbox: queued {} until the backend is ready", id);
+        emit(&app);
+        return Ok(Submission::Queued {
+            item: Box::new(item),
+        });
+    }
+    match deliver(&app, &id).await? {
+        Submission::Queued { item } if item.status == QueueStatus::Held => {
+            modify(&app, |outbox| {
+                outbox.entries.retain(|queued| queued.id != id);
+                Ok(())
+            })?;
+            emit(&app);
+            Err(item.rejection.map(|r| r.message).unwrap_or_default())
+        }
+        submission => Ok(submission),
+    }
+}
+
+#[tauri::command]
+pub fn list_outbox(app: AppHandle) -> Result<Vec<QueuedEntry>, String> {
+    let outbox = app.state::<Outbox>();
+    let _file = outbox.file.lock().unwrap();
+    Ok(load(&app)?.entries)
+}
+
+/// Replace a queued entry's content, author, intent and metadata and queue it
+/// again. A signed entry is signed again, so its identity must be unlocked.
+#[tauri::command]
+pub fn update_outbox_entry(
+    app: AppHandle,
+    id: String,
+    entry: Value,
+) -> Result<QueuedEntry, String> {
+    if app.state::<Outbox>().sending.lock().unwrap().contains(&id) {
+        return Err("The entry is being sent.".into());
+    }
+    let item = modify(&app, |outbox| {
+        let item = find(outbox, &id)?;
+        let entry = unsigned(entry)?;
+        item.entry = match &item.signed_with {
+            Some(identity) => keystore::sign_entry(app.clone(), identity.clone(), entry)?,
+            None => entry,
+        };
+        item.status = QueueStatus::Queued;
+        item.attempts = 0;
+        item.next_attempt = now();
+        item.last_error = None;
+        item.rejection = None;
+        Ok(item.clone())
+    })?;
+    emit(&app);
+    Ok(item)
+}
+
+/// Send a queued or held entry now, whatever its backoff.
+#[tauri::command]
+pub async fn retry_outbox_entry(app: AppHandle, id: String) -> Result<Submission, String> {
+    if !backend_ready(&app) {
+        return Err("The backend is not running.".into());
+    }
+    deliver(&app, &id).await
+}
+
+#[tauri::command]
+pub fn discard_outbox_entry(app: AppHandle, id: String) -> Result<(), String> {
+    if app.state::<Outbox>().sending.lock().unwrap().contains(&id) {
+        return Err("The entry is being sent.".into());
+    }
+    modify(&app, |outbox| {
+        outbox.entries.retain(|item| item.id != id);
+        Ok(())
+    })?;
+    log_info!("[Shell] Outbox: discarded {}", id);
+    emit(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn queued(status: QueueStatus, next_attempt: String) -> QueuedEntry {
+        QueuedEntry {
+            id: "id".into(),
+            entry: json!({}),
+            signed_with: None,
+            queued_at: now(),
+            status,
+            attempts: 1,
+            next_attempt,
+            last_error: None,
+            rejection: None,
+        }
+    }
+
+    /// A timestamp `secs` ago, as Python's `datetime.utcnow().isoformat()` has it
+    fn signed_ago(secs: i64) -> Value {
+        let at = Utc::now().naive_utc() - chrono::Duration::seconds(secs);
+        json!({ "timestamp": at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string() })
+    }
+
+    #[test]
+    fn classifies_rejections() {
+        let kind = |status, body| rejection(status, body, None).kind;
+        assert_eq!(kind(429, json!({})), RejectionKind::RateLimit);
+        assert_eq!(
+            kind(400, json!({ "reason": "rate_limit" })),
+            RejectionKind::RateLimit
+        );
+        assert_eq!(
+            kind(409, json!({ "reason": "duplicate" })),
+            RejectionKind::Duplicate
+        );
+        assert_eq!(
+            kind(400, json!({ "reason": "quality_check_failed" })),
+            RejectionKind::Quality
+        );
+        assert_eq!(
+            kind(400, json!({ "reason": "quality_needs_improvement" })),
+            RejectionKind::Quality
+        );
+        assert_eq!(
+            kind(400, json!({ "reason": "invalid_timestamp" })),
+            RejectionKind::Timestamp
+        );
+        assert_eq!(
+            kind(403, json!({ "error": "Forbidden" })),
+            RejectionKind::Invalid
+        );
+    }
+
+    #[test]
+    fn keeps_the_backends_message_and_wait() {
+        let body = json!({ "reason": "rate_limit", "message": "Slow down", "retry_after": 12 });
+        let limited = rejection(429, body.clone(), None);
+        assert_eq!(limited.message, "Slow down");
+        assert_eq!(limited.retry_after, Some(12));
+        assert_eq!(limited.details, body);
+        // The Retry-After header wins over the body
+        assert_eq!(rejection(429, body, Some(3)).retry_after, Some(3));
+        assert_eq!(
+            rejection(429, json!({}), None).retry_after,
+            Some(DEFAULT_RETRY_AFTER_SECS)
+        );
+
+        let invalid = rejection(
+            400,
+            json!({ "error": "Bad metadata", "retry_after": 5 }),
+            None,
+        );
+        assert_eq!(invalid.message, "Bad metadata");
+        assert_eq!(invalid.retry_after, None);
+        assert_eq!(
+            rejection(500, json!(null), None).message,
+            "The backend rejected the entry."
+        );
+    }
+
+    #[test]
+    fn backs_off_exponentially_up_to_the_cap() {
+        let waits: Vec<i64> = (0..10).map(backoff_secs).collect();
+        assert_eq!(waits, [5, 5, 10, 20, 40, 80, 160, 320, 600, 600]);
+        assert_eq!(backoff_secs(u32::MAX), MAX_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn is_due_once_its_time_has_come() {
+        assert!(is_due(&queued(QueueStatus::Queued, after_secs(-1))));
+        assert!(!is_due(&queued(QueueStatus::Queued, after_secs(60))));
+        // An unreadable time doesn't keep it waiting forever
+        assert!(is_due(&queued(QueueStatus::Queued, "soon".into())));
+        assert!(!is_due(&queued(QueueStatus::Held, after_secs(-1))));
+    }
+
+    #[test]
+    fn unsigned_strips_the_signature_and_timestamp() {
+        let entry = json!({
+            "content": "Alice pays Bob.",
+            "author": "alice",
+            "intent": "Payment",
+            "timestamp": "2026-01-01T00:00:00.000000",
+            "metadata": {
+                "signature": "c2ln",
+                "public_key": "a2V5",
+                "signer_fingerprint": "0123",
+                "project": "alpha",
+            },
+        });
+        assert_eq!(
+            unsigned(entry).unwrap(),
+            json!({
+                "content": "Alice pays Bob.",
+                "author": "alice",
+                "intent": "Payment",
+                "metadata": { "project": "alpha" },
+            })
+        );
+        assert!(unsigned(json!({ "content": "x", "author": "a" })).is_err());
+        assert!(unsigned(json!([])).is_err());
+    }
+
+    #[test]
+    fn renews_signatures_before_the_backend_would_reject_them() {
+        assert!(!signature_expiring(&signed_ago(10)));
+        assert!(!signature_expiring(&signed_ago(RESIGN_AFTER_SECS - 5)));
+        // Past RESIGN_AFTER_SECS but still inside the backend's 300s drift
+        assert!(signature_expiring(&signed_ago(RESIGN_AFTER_SECS + 5)));
+        assert!(signature_expiring(&signed_ago(300)));
+        assert!(!signature_expiring(&json!({})));
+    }
+}
@@ -0,0 +1,17 @@
+//! Loopback port allocation for the backend sidecar.
+
+use std::io;
+use std::net::{Ipv4Addr, TcpListener};
+
+/// Ask the OS for a free loopback port.
+///
+/// The listener is dropped before the sidecar binds, so another process could
+/// in principle grab the port in between; the supervisor's restart handles that.
+pub fn pick_free() -> io::Result<u16> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+pub fn is_free(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+}
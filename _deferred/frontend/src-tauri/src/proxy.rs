@@ -0,0 +1,150 @@
+//! `api_request` command: forwards webview API calls to the backend sidecar.
+//!
+//! The webview never talks to `http://127.0.0.1:<port>` itself; every API
+//! call comes through here, which is where the auth header, timeouts,
+//! retries and request logging live.
+//!
+//! This was first an `nlc://` custom URI scheme, as originally planned. It is
+//! a command instead because Tauri 1 only has synchronous protocol handlers
+//! (`register_uri_scheme_protocol`), which run on the main thread: every
+//! request would freeze the UI until the backend answered, for up to a
+//! minute while an entry is validated. Commands run on the async runtime.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::auth;
+use crate::backend;
+use crate::startup::StartupError;
+
+/// Generous because entry validation can wait on an LLM.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Retries for idempotent requests that could not reach the backend at all,
+/// e.g. while the supervisor is restarting it.
+const MAX_RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Response headers worth passing back to the webview.
+const FORWARDED_HEADERS: &[&str] = &["content-type", "retry-after"];
+
+/// Holds the HTTP client, built on first use so that failing to build it
+/// is a `StartupError` (see `backend::start`) rather than a panic.
+#[derive(Default)]
+pub struct Proxy {
+    client: Mutex<Option<reqwest::Client>>,
+}
+
+impl Proxy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The client requests go through, for the shell's own calls to the backend.
+    pub fn client(&self) -> Result<reqwest::Client, StartupError> {
+        let mut client = self.client.lock().unwrap();
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let built = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| StartupError::HttpClient(e.to_string()))?;
+        *client = Some(built.clone());
+        Ok(built)
+    }
+}
+
+/// What `fetchAPI` rebuilds into a fetch `Response`.
+#[derive(Serialize)]
+pub struct ApiResponse {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+#[tauri::command]
+pub async fn api_request(
+    app: AppHandle,
+    method: String,
+    path: String,
+    body: Option<String>,
+    content_type: Option<String>,
+) -> Result<ApiResponse, String> {
+    // The path is appended to the backend's origin and must not change it
+    if !path.starts_with('/') {
+        return Err(format!("Invalid API path: {}", path));
+    }
+    let method = reqwest::Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+    let idempotent = matches!(method, reqwest::Method::GET | reqwest::Method::HEAD);
+    let content_type = content_type.unwrap_or_else(|| "application/json".to_string());
+
+    let (base_url, api_token) = {
+        let state = backend::state(&app);
+        let state = state.lock().unwrap();
+        (state.base_url(), state.api_token.clone())
+    };
+    let client = app.state::<Proxy>().client().map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    let mut attempt = 0;
+    let result = loop {
+        let mut outgoing = client
+            .request(method.clone(), format!("{}{}", base_url, path))
+            .header("content-type", &content_type);
+        // An external backend may run without auth and without a key
+        if !api_token.is_empty() {
+            outgoing = outgoing.header(auth::HEADER, &api_token);
+        }
+        if let Some(body) = &body {
+            outgoing = outgoing.body(body.clone());
+        }
+
+        match outgoing.send().await {
+            Err(e) if e.is_connect() && idempotent && attempt < MAX_RETRIES => {
+                attempt += 1;
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            Err(e) => break Err(e),
+            Ok(response) => {
+                let status = response.status().as_u16();
+                let headers: HashMap<String, String> = FORWARDED_HEADERS
+                    .iter()
+                    .filter_map(|name| {
+                        let value = response.headers().get(*name)?.to_str().ok()?;
+                        Some((name.to_string(), value.to_string()))
+                    })
+                    .collect();
+                break response.text().await.map(|body| ApiResponse { status, headers, body });
+            }
+        }
+    };
+    let elapsed = started.elapsed().as_millis();
+
+    match result {
+        Ok(response) => {
+            log_info!("[Proxy] {} {} -> {} ({} ms)", method, path, response.status, elapsed);
+            Ok(response)
+        }
+        Err(e) => {
+            log_error!("[Proxy] {} {} failed after {} ms: {}", method, path, elapsed, e);
+            if e.is_timeout() {
+                Ok(error_response(504, "Backend request timed out"))
+            } else {
+                Ok(error_response(503, "Backend unavailable"))
+            }
+        }
+    }
+}
+
+fn error_response(status: u16, message: &str) -> ApiResponse {
+    let body = serde_json::json!({ "error": message, "status": "unavailable" });
+    ApiResponse {
+        status,
+        headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+        body: body.to_string(),
+    }
+}
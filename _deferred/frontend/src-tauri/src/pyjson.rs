@@ -0,0 +1,216 @@
+//! JSON serialisation byte-compatible with Python's `json.dumps`.
+//!
+//! Block hashes and entry signatures are computed by the backend over
+//! `json.dumps(..., sort_keys=True)` output, so checking them in Rust means
+//! producing exactly the same bytes: Python's separators, keys sorted by code
+//! point, `ensure_ascii` escaping (surrogate pairs above U+FFFF, DEL
+//! included) and floats written the way Python's `repr` writes them.
+
+use std::fmt::Write;
+
+use serde_json::{Number, Value};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    pub item_separator: &'static str,
+    pub key_separator: &'static str,
+    pub ensure_ascii: bool,
+}
+
+/// `json.dumps(value, sort_keys=True)`
+pub const DEFAULT: Style = Style {
+    item_separator: ", ",
+    key_separator: ": ",
+    ensure_ascii: true,
+};
+
+/// `json.dumps(value, sort_keys=True, separators=(",", ":"))`
+pub const COMPACT: Style = Style {
+    item_separator: ",",
+    key_separator: ":",
+    ensure_ascii: true,
+};
+
+/// Serialise `value` with sorted keys in the given style.
+pub fn dumps(value: &Value, style: Style) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, style);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value, style: Style) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(true) => out.push_str("true"),
+        Value::Bool(false) => out.push_str("false"),
+        Value::Number(number) => write_number(out, number),
+        Value::String(s) => write_string(out, s, style.ensure_ascii),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(style.item_separator);
+                }
+                write_value(out, item, style);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            // Byte order of UTF-8 is code point order, which is what Python sorts by
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push_str(style.item_separator);
+                }
+                write_string(out, key, style.ensure_ascii);
+                out.push_str(style.key_separator);
+                write_value(out, item, style);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(out: &mut String, number: &Number) {
+    if let Some(n) = number.as_i64() {
+        let _ = write!(out, "{}", n);
+    } else if let Some(n) = number.as_u64() {
+        let _ = write!(out, "{}", n);
+    } else if let Some(n) = number.as_f64() {
+        out.push_str(&float_repr(n));
+    }
+}
+
+/// Python's `float.__repr__`: the shortest digits that round-trip, in
+/// positional notation for decimal exponents from -4 to 15 and scientific
+/// notation (with at least two exponent digits) otherwise.
+pub fn float_repr(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".into();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.into();
+    }
+    if value == 0.0 {
+        return if value.is_sign_negative() { "-0.0" } else { "0.0" }.into();
+    }
+
+    // `{:e}` gives the shortest round-trip digits, e.g. "-1.2345e-7". When
+    // two such strings are equally close Python takes the correctly rounded
+    // one (ties to even), which fixed-precision formatting also produces
+    let shortest = format!("{:e}", value);
+    let precision = shortest
+        .split('e')
+        .next()
+        .map_or(0, |mantissa| mantissa.chars().filter(char::is_ascii_digit).count())
+        .saturating_sub(1);
+    let rounded = format!("{:.*e}", precision, value);
+    let scientific = if rounded.parse::<f64>() == Ok(value) {
+        rounded
+    } else {
+        shortest
+    };
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", mantissa),
+    };
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+
+    let mut out = String::from(sign);
+    if (-4..16).contains(&exponent) {
+        if exponent < 0 {
+            out.push_str("0.");
+            out.push_str(&"0".repeat((-exponent - 1) as usize));
+            out.push_str(&digits);
+        } else {
+            let point = exponent as usize + 1;
+            if digits.len() > point {
+                out.push_str(&digits[..point]);
+                out.push('.');
+                out.push_str(&digits[point..]);
+            } else {
+                out.push_str(&digits);
+                out.push_str(&"0".repeat(point - digits.len()));
+                out.push_str(".0");
+            }
+        }
+    } else {
+        out.push_str(&digits[..1]);
+        if digits.len() > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        let _ = write!(
+            out,
+            "e{}{:02}",
+            if exponent < 0 { '-' } else { '+' },
+            exponent.abs()
+        );
+    }
+    out
+}
+
+fn write_string(out: &mut String, s: &str, ensure_ascii: bool) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            // Python's ASCII escaper only lets ' ' to '~' through
+            c if ensure_ascii && c > '~' => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    let _ = write!(out, "\\u{:04x}", unit);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `json.dumps` output for each case; see tests/fixtures/generate.py
+    const CASES: &str = include_str!("../tests/fixtures/pyjson.json");
+
+    fn cases() -> Value {
+        serde_json::from_str(CASES).unwrap()
+    }
+
+    #[test]
+    fn floats_match_python_repr() {
+        for case in cases()["floats"].as_array().unwrap() {
+            let value = f64::from_bits(case["bits"].as_u64().unwrap());
+            assert_eq!(float_repr(value), case["repr"].as_str().unwrap(), "{:e}", value);
+        }
+    }
+
+    #[test]
+    fn dumps_matches_python() {
+        let unicode = Style {
+            ensure_ascii: false,
+            ..DEFAULT
+        };
+        for case in cases()["values"].as_array().unwrap() {
+            let value = &case["value"];
+            assert_eq!(dumps(value, DEFAULT), case["default"].as_str().unwrap());
+            assert_eq!(dumps(value, COMPACT), case["compact"].as_str().unwrap());
+            assert_eq!(dumps(value, unicode), case["unicode"].as_str().unwrap());
+        }
+    }
+}
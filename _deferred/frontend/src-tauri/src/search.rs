@@ -0,0 +1,712 @@
+//! Full-text search over the saved chain's entries, without the backend.
+//!
+//! `/entries/search` only finds a substring of the content, and
+//! `/search/semantic` needs the embedding model. This indexes every entry's
+//! content, intent and metadata from the workspace's chain file (as `offline`
+//! reads it) and ranks the matches with BM25. Words match by their English
+//! stem, so "contract" also finds "contracts" and "contracted".
+//!
+//! A query is words, all of which must appear, and `"quoted phrases"`. A word
+//! or phrase is looked for in one field only after `content:`, `intent:` or
+//! `metadata:`, and `author:` keeps one author's entries.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use rust_stemmers::{Algorithm, Stemmer};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::encryption::ENCRYPTED_PREFIX;
+use crate::offline::{self, Loaded};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+
+// Okapi BM25's usual parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// A snippet's length, and how much of it comes before the first match, in
+/// characters
+const SNIPPET_CHARS: usize = 200;
+const SNIPPET_LEAD: usize = 60;
+
+/// The index of the last chain searched.
+#[derive(Default)]
+pub struct SearchIndex {
+    built: Mutex<Option<Built>>,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Clone)]
+struct Built {
+    chain: Arc<Loaded>,
+    index: Arc<Index>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Content,
+    Intent,
+    Metadata,
+}
+
+impl Field {
+    const ALL: [Field; 3] = [Field::Content, Field::Intent, Field::Metadata];
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Content => "content",
+            Field::Intent => "intent",
+            Field::Metadata => "metadata",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Field> {
+        Field::ALL
+            .into_iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name))
+    }
+
+    /// A match in the short intent says more than one in the content
+    fn weight(self) -> f64 {
+        match self {
+            Field::Content => 1.0,
+            Field::Intent => 2.0,
+            Field::Metadata => 0.5,
+        }
+    }
+}
+
+/// The fields a word is looked for in
+fn fields(field: Option<Field>) -> &'static [Field] {
+    match field {
+        Some(Field::Content) => &[Field::Content],
+        Some(Field::Intent) => &[Field::Intent],
+        Some(Field::Metadata) => &[Field::Metadata],
+        None => &Field::ALL,
+    }
+}
+
+fn text(entry: &Value, field: Field) -> String {
+    match field {
+        Field::Content | Field::Intent => entry[field.name()].as_str().unwrap_or_default().into(),
+        Field::Metadata => metadata_text(&entry["metadata"]),
+    }
+}
+
+/// The metadata as `key: value` pairs, nested values flattened. Encrypted
+/// fields can't be searched and are left out.
+fn metadata_text(metadata: &Value) -> String {
+    let Value::Object(fields) = metadata else {
+        return String::new();
+    };
+    let mut pairs = Vec::new();
+    for (key, value) in fields {
+        let mut words = Vec::new();
+        flatten(value, &mut words);
+        if !words.is_empty() {
+            pairs.push(format!("{}: {}", key, words.join(" ")));
+        }
+    }
+    pairs.join("; ")
+}
+
+fn flatten(value: &Value, words: &mut Vec<String>) {
+    match value {
+        Value::Null => {}
+        Value::String(s) if s.starts_with(ENCRYPTED_PREFIX) => {}
+        Value::String(s) => words.push(s.clone()),
+        Value::Array(items) => items.iter().for_each(|item| flatten(item, words)),
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                words.push(key.clone());
+                flatten(value, words);
+            }
+        }
+        other => words.push(other.to_string()),
+    }
+}
+
+/// A word of a text, by its stem, and where it is
+struct Token {
+    stem: String,
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(stemmer: &Stemmer, text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    // A space at the end finishes the last word
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphanumeric(), word_start) {
+            (true, None) => word_start = Some(i),
+            (false, Some(start)) => {
+                let word = text[start..i].to_lowercase();
+                tokens.push(Token {
+                    stem: stemmer.stem(&word).into_owned(),
+                    start,
+                    end: i,
+                });
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// One part of a query; an entry must match all of them.
+#[derive(Debug, PartialEq)]
+enum Clause {
+    /// These words in this order, in `field` or any field
+    Phrase {
+        field: Option<Field>,
+        stems: Vec<String>,
+    },
+    /// Entries by this author, ignoring case
+    Author(String),
+}
+
+fn parse(stemmer: &Stemmer, query: &str) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+    let mut rest = query.trim_start();
+    while !rest.is_empty() {
+        // `name:` before a word or phrase
+        let colon = rest
+            .find(':')
+            .filter(|&colon| colon > 0 && rest[..colon].chars().all(char::is_alphabetic));
+        let body = colon.map_or(rest, |colon| &rest[colon + 1..]);
+        let (value, next) = match body.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((phrase, next)) => (phrase, next),
+                None => (quoted, ""),
+            },
+            None => {
+                let end = body.find(char::is_whitespace).unwrap_or(body.len());
+                body.split_at(end)
+            }
+        };
+        let taken = &rest[..rest.len() - next.len()];
+        rest = next.trim_start();
+
+        let name = colon.map(|colon| &taken[..colon]);
+        let (field, words) = match name {
+            Some(name) if name.eq_ignore_ascii_case("author") => {
+                let author = value.trim().to_lowercase();
+                if !author.is_empty() {
+                    clauses.push(Clause::Author(author));
+                }
+                continue;
+            }
+            Some(name) => match Field::parse(name) {
+                Some(field) => (Some(field), value),
+                // Not a field, so just text
+                None => (None, taken),
+            },
+            None => (None, value),
+        };
+        let stems = stems(stemmer, words);
+        // Punctuation alone has no words to look for
+        if !stems.is_empty() {
+            clauses.push(Clause::Phrase { field, stems });
+        }
+    }
+    clauses
+}
+
+fn stems(stemmer: &Stemmer, text: &str) -> Vec<String> {
+    tokenize(stemmer, text)
+        .into_iter()
+        .map(|token| token.stem)
+        .collect()
+}
+
+/// Where an entry is in the chain, and how many words each of its fields has
+struct Doc {
+    block: usize,
+    entry: usize,
+    /// Lowercased
+    author: String,
+    lengths: [usize; 3],
+}
+
+/// Where a stem is in one field of one entry
+struct Posting {
+    doc: usize,
+    field: Field,
+    positions: Vec<usize>,
+}
+
+struct Index {
+    /// In chain order
+    docs: Vec<Doc>,
+    /// Ordered by doc
+    postings: HashMap<String, Vec<Posting>>,
+    average_lengths: [f64; 3],
+}
+
+impl Index {
+    fn build(blocks: &[Value]) -> Index {
+        let stemmer = Stemmer::create(Algorithm::English);
+        let mut docs = Vec::new();
+        let mut postings: HashMap<String, Vec<Posting>> = HashMap::new();
+        let mut totals = [0; 3];
+        for (block_position, block) in blocks.iter().enumerate() {
+            let entries = block["entries"].as_array().map_or(&[][..], Vec::as_slice);
+            for (entry_position, entry) in entries.iter().enumerate() {
+                let doc = docs.len();
+                let mut lengths = [0; 3];
+                for field in Field::ALL {
+                    let tokens = tokenize(&stemmer, &text(entry, field));
+                    lengths[field as usize] = tokens.len();
+                    totals[field as usize] += tokens.len();
+                    let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+                    for (position, token) in tokens.into_iter().enumerate() {
+                        positions.entry(token.stem).or_default().push(position);
+                    }
+                    for (stem, positions) in positions {
+                        postings.entry(stem).or_default().push(Posting {
+                            doc,
+                            field,
+                            positions,
+                        });
+                    }
+                }
+                docs.push(Doc {
+                    block: block_position,
+                    entry: entry_position,
+                    author: entry["author"].as_str().unwrap_or_default().to_lowercase(),
+                    lengths,
+                });
+            }
+        }
+        let count = docs.len().max(1) as f64;
+        Index {
+            docs,
+            postings,
+            average_lengths: totals.map(|total| total as f64 / count),
+        }
+    }
+
+    fn postings(&self, stem: &str) -> &[Posting] {
+        self.postings.get(stem).map_or(&[], Vec::as_slice)
+    }
+
+    /// The entries a phrase is in, within `fields`
+    fn phrase_docs(&self, stems: &[String], fields: &[Field]) -> HashSet<usize> {
+        let lookups: Vec<HashMap<(usize, usize), &[usize]>> = stems
+            .iter()
+            .map(|stem| {
+                self.postings(stem)
+                    .iter()
+                    .filter(|posting| fields.contains(&posting.field))
+                    .map(|posting| {
+                        (
+                            (posting.doc, posting.field as usize),
+                            posting.positions.as_slice(),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        let Some((first, following)) = lookups.split_first() else {
+            return HashSet::new();
+        };
+        first
+            .iter()
+            .filter(|(key, positions)| {
+                positions.iter().any(|&start| {
+                    following.iter().enumerate().all(|(i, lookup)| {
+                        lookup
+                            .get(*key)
+                            .is_some_and(|next| next.binary_search(&(start + i + 1)).is_ok())
+                    })
+                })
+            })
+            .map(|(&(doc, _), _)| doc)
+            .collect()
+    }
+
+    /// The entries that match every clause, with their scores, best first
+    fn search(&self, clauses: &[Clause]) -> Vec<(usize, f64)> {
+        let mut matching: Option<HashSet<usize>> = None;
+        for clause in clauses {
+            let docs = match clause {
+                Clause::Author(author) => (0..self.docs.len())
+                    .filter(|&doc| self.docs[doc].author == *author)
+                    .collect(),
+                Clause::Phrase { field, stems } => self.phrase_docs(stems, fields(*field)),
+            };
+            matching = Some(match matching {
+                Some(matching) => matching.intersection(&docs).copied().collect(),
+                None => docs,
+            });
+        }
+
+        let terms: Vec<(&[Posting], f64, &[Field])> = clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                Clause::Phrase { field, stems } => Some((stems, fields(*field))),
+                Clause::Author(_) => None,
+            })
+            .flat_map(|(stems, fields)| {
+                stems.iter().map(move |stem| {
+                    let postings = self.postings(stem);
+                    (postings, self.idf(postings), fields)
+                })
+            })
+            .collect();
+        let mut ranked: Vec<(usize, f64)> = matching
+            .unwrap_or_default()
+            .into_iter()
+            .map(|doc| (doc, self.score(doc, &terms)))
+            .collect();
+        // Equally good matches newest first
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.cmp(&a.0)));
+        ranked
+    }
+
+    /// How rare a stem is among the entries
+    fn idf(&self, postings: &[Posting]) -> f64 {
+        let count = self.docs.len() as f64;
+        let with_stem = postings.windows(2).filter(|w| w[0].doc != w[1].doc).count()
+            + usize::from(!postings.is_empty());
+        let with_stem = with_stem as f64;
+        (1.0 + (count - with_stem + 0.5) / (with_stem + 0.5)).ln()
+    }
+
+    /// BM25 summed over the fields, each by its weight
+    fn score(&self, doc: usize, terms: &[(&[Posting], f64, &[Field])]) -> f64 {
+        let mut score = 0.0;
+        for &(postings, idf, fields) in terms {
+            let from = postings.partition_point(|posting| posting.doc < doc);
+            for posting in postings[from..]
+                .iter()
+                .take_while(|posting| posting.doc == doc)
+            {
+                if !fields.contains(&posting.field) {
+                    continue;
+                }
+                let field = posting.field as usize;
+                let frequency = posting.positions.len() as f64;
+                let length = self.docs[doc].lengths[field] as f64 / self.average_lengths[field];
+                score += posting.field.weight() * idf * frequency * (K1 + 1.0)
+                    / (frequency + K1 * (1.0 - B + B * length));
+            }
+        }
+        score
+    }
+}
+
+/// The stems to highlight in each field
+fn stems_to_highlight(clauses: &[Clause]) -> [HashSet<&str>; 3] {
+    let mut highlighted: [HashSet<&str>; 3] = Default::default();
+    for clause in clauses {
+        if let Clause::Phrase { field, stems } = clause {
+            for field in fields(*field) {
+                highlighted[*field as usize].extend(stems.iter().map(String::as_str));
+            }
+        }
+    }
+    highlighted
+}
+
+#[derive(serde::Serialize)]
+struct Segment {
+    text: String,
+    highlight: bool,
+}
+
+impl Segment {
+    fn plain(text: &str) -> Segment {
+        Segment {
+            text: text.into(),
+            highlight: false,
+        }
+    }
+}
+
+/// `text` split into the words that matched and the rest, or `None` if none
+/// did. With `cut`, only the part around the first match is kept.
+fn highlight(
+    stemmer: &Stemmer,
+    text: &str,
+    stems: &HashSet<&str>,
+    cut: bool,
+) -> Option<Vec<Segment>> {
+    if stems.is_empty() {
+        return None;
+    }
+    let matched: Vec<Token> = tokenize(stemmer, text)
+        .into_iter()
+        .filter(|token| stems.contains(token.stem.as_str()))
+        .collect();
+    let first = matched.first()?;
+    let (start, end) = if cut {
+        snippet(text, first.start)
+    } else {
+        (0, text.len())
+    };
+
+    let mut segments = Vec::new();
+    if start > 0 {
+        segments.push(Segment::plain("…"));
+    }
+    let mut at = start;
+    for token in matched.iter().take_while(|token| token.end <= end) {
+        if token.start > at {
+            segments.push(Segment::plain(&text[at..token.start]));
+        }
+        segments.push(Segment {
+            text: text[token.start..token.end].into(),
+            highlight: true,
+        });
+        at = token.end;
+    }
+    if end > at {
+        segments.push(Segment::plain(&text[at..end]));
+    }
+    if end < text.len() {
+        segments.push(Segment::plain("…"));
+    }
+    Some(segments)
+}
+
+/// Up to SNIPPET_CHARS of `text` from a little before `at`, cut between words
+fn snippet(text: &str, at: usize) -> (usize, usize) {
+    let mut start = text[..at]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_LEAD - 1)
+        .map_or(0, |(i, _)| i);
+    if start > 0 {
+        if let Some((_, after)) = text[start..at].split_once(char::is_whitespace) {
+            start = at - after.len();
+        }
+    }
+    let mut end = text[start..]
+        .char_indices()
+        .nth(SNIPPET_CHARS)
+        .map_or(text.len(), |(i, _)| start + i);
+    if end < text.len() {
+        if let Some((before, _)) = text[at..end].rsplit_once(char::is_whitespace) {
+            end = at + before.len();
+        }
+    }
+    (start, end)
+}
+
+/// The current workspace's chain and its index, built again only if the
+/// chain was read again.
+async fn current(app: &AppHandle) -> Result<(Arc<Loaded>, Arc<Index>), String> {
+    let chain = offline::current(app).await?;
+    let built = app.state::<SearchIndex>().built.lock().unwrap().clone();
+    if let Some(built) = built.filter(|b| Arc::ptr_eq(&b.chain, &chain)) {
+        return Ok((chain, built.index));
+    }
+    let source = chain.clone();
+    // Stemming every entry of a long chain takes a moment
+    let index = tauri::async_runtime::spawn_blocking(move || Index::build(source.blocks()))
+        .await
+        .map_err(|e| e.to_string())?;
+    let index = Arc::new(index);
+    *app.state::<SearchIndex>().built.lock().unwrap() = Some(Built {
+        chain: chain.clone(),
+        index: index.clone(),
+    });
+    Ok((chain, index))
+}
+
+/// Entries that match `query`, best first. Each is the entry as the chain
+/// file has it, plus `block_index`, `entry_index`, `block_hash`, its BM25
+/// `score` and `highlights`: for every field that matched, its text (a
+/// snippet of the content and metadata) as `{ text, highlight }` segments.
+#[tauri::command]
+pub async fn search_entries_local(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Value, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let stemmer = Stemmer::create(Algorithm::English);
+    let clauses = parse(&stemmer, &query);
+    if clauses.is_empty() {
+        return Err("Enter a word or phrase to search for.".into());
+    }
+    let (chain, index) = current(&app).await?;
+    let ranked = index.search(&clauses);
+    let highlighted = stems_to_highlight(&clauses);
+
+    let results: Vec<Value> = ranked
+        .iter()
+        .take(limit)
+        .map(|&(doc, score)| {
+            let doc = &index.docs[doc];
+            let block = &chain.blocks()[doc.block];
+            let entry = &block["entries"][doc.entry];
+            let mut highlights = serde_json::Map::new();
+            for field in Field::ALL {
+                let stems = &highlighted[field as usize];
+                let cut = field != Field::Intent;
+                if let Some(segments) = highlight(&stemmer, &text(entry, field), stems, cut) {
+                    highlights.insert(field.name().into(), json!(segments));
+                }
+            }
+            let mut result = entry.clone();
+            if let Value::Object(fields) = &mut result {
+                fields.insert("block_index".into(), block["index"].clone());
+                fields.insert("entry_index".into(), doc.entry.into());
+                fields.insert("block_hash".into(), block["hash"].clone());
+                fields.insert("score".into(), json!(score));
+                fields.insert("highlights".into(), Value::Object(highlights));
+            }
+            result
+        })
+        .collect();
+    Ok(json!({
+        "query": query,
+        "count": results.len(),
+        "total": ranked.len(),
+        "search_type": "local",
+        "results": results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stemmer() -> Stemmer {
+        Stemmer::create(Algorithm::English)
+    }
+
+    fn phrase(field: Option<Field>, text: &str) -> Clause {
+        Clause::Phrase {
+            field,
+            stems: stems(&stemmer(), text),
+        }
+    }
+
+    fn entry(author: &str, intent: &str, content: &str) -> Value {
+        json!({ "author": author, "intent": intent, "content": content, "metadata": {} })
+    }
+
+    /// Two blocks of entries, as `Loaded::blocks` has them
+    fn chain() -> Vec<Value> {
+        vec![
+            json!({ "index": 0, "entries": [
+                entry("Alice", "Payment terms", "The supplier ships the goods every month."),
+                entry("Bob", "Supply agreement", "Payment is due thirty days after delivery."),
+            ]}),
+            json!({ "index": 1, "entries": [
+                entry("alice", "Penalty clause", "A late delivery costs the supplier a fee."),
+                entry("Carol", "Delivery schedule", "Delivery is never late, and fees are waived."),
+            ]}),
+        ]
+    }
+
+    #[test]
+    fn parses_words_fields_and_authors() {
+        assert_eq!(
+            parse(&stemmer(), "  author:Alice intent:payments contracts  "),
+            vec![
+                Clause::Author("alice".into()),
+                phrase(Some(Field::Intent), "payment"),
+                phrase(None, "contract"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_an_unclosed_phrase_to_the_end() {
+        assert_eq!(
+            parse(&stemmer(), r#"fee content:"late delivery costs"#),
+            vec![
+                phrase(None, "fee"),
+                phrase(Some(Field::Content), "late delivery costs")
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_an_unknown_name_as_text() {
+        assert_eq!(parse(&stemmer(), "foo:bar"), vec![phrase(None, "foo bar")]);
+        // Nor is a colon after a digit or at the start a field
+        assert_eq!(parse(&stemmer(), "10:30"), vec![phrase(None, "10 30")]);
+        assert!(parse(&stemmer(), "author: -- ").is_empty());
+    }
+
+    #[test]
+    fn finds_phrases_in_order_and_in_one_field() {
+        let index = Index::build(&chain());
+        let late_delivery = stems(&stemmer(), "late delivery");
+        // "Delivery is never late" has both words, but not together
+        assert_eq!(
+            index.phrase_docs(&late_delivery, &Field::ALL),
+            HashSet::from([2])
+        );
+        assert!(index
+            .phrase_docs(&late_delivery, &[Field::Intent])
+            .is_empty());
+        // The last word of the intent doesn't run on into the content
+        let across_fields = stems(&stemmer(), "schedule delivery");
+        assert!(index.phrase_docs(&across_fields, &Field::ALL).is_empty());
+        assert!(index.phrase_docs(&[], &Field::ALL).is_empty());
+    }
+
+    #[test]
+    fn ranks_an_intent_match_above_a_content_match() {
+        let index = Index::build(&chain());
+        let ranked = index.search(&parse(&stemmer(), "payment"));
+        let docs: Vec<usize> = ranked.iter().map(|&(doc, _)| doc).collect();
+        assert_eq!(docs, [0, 1]);
+        assert!(ranked[0].1 > ranked[1].1);
+
+        // Only where the clause looks
+        let ranked = index.search(&parse(&stemmer(), "content:payment"));
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0, 1);
+    }
+
+    #[test]
+    fn keeps_entries_matching_every_clause() {
+        let index = Index::build(&chain());
+        let ranked = index.search(&parse(&stemmer(), "author:ALICE supplier"));
+        let mut docs: Vec<usize> = ranked.iter().map(|&(doc, _)| doc).collect();
+        docs.sort();
+        assert_eq!(docs, [0, 2]);
+        assert!(index
+            .search(&parse(&stemmer(), "author:bob supplier"))
+            .is_empty());
+    }
+
+    #[test]
+    fn cuts_multibyte_snippets_between_words() {
+        let before = "naïve café über ".repeat(10);
+        let text = format!("{}contract {}", before, "señor niño ".repeat(30));
+        let at = before.len();
+        let (start, end) = snippet(&text, at);
+        // Slicing panics unless both are on character boundaries
+        let cut = &text[start..end];
+        let lead = text[start..at].chars().count();
+        assert!(lead > 0 && lead <= SNIPPET_LEAD, "{}", lead);
+        assert!(cut.starts_with(|c: char| c.is_alphabetic()), "{}", cut);
+        assert!(cut.chars().count() <= SNIPPET_CHARS);
+        assert!(end < text.len() && text[end..].starts_with(char::is_whitespace));
+
+        // Near the start, the snippet starts there
+        let (start, _) = snippet(&text, "naïve ".len());
+        assert_eq!(start, 0);
+        // Near the end, it runs to it
+        let (_, end) = snippet(&text, text.len() - "niño ".len());
+        assert_eq!(end, text.len());
+    }
+}
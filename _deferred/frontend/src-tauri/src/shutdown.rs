@@ -0,0 +1,142 @@
+//! Graceful backend shutdown.
+//!
+//! `run_server.py` handles SIGTERM by refusing new requests, waiting up to
+//! `SHUTDOWN_TIMEOUT` seconds for in-flight ones and saving the chain. We ask
+//! for that (SIGTERM on Unix, `POST /shutdown` on Windows where there is no
+//! SIGTERM to send), wait for the process to exit, and only force-kill it if
+//! it overstays.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Manager};
+
+use crate::backend::{self, BackendState, BackendStatus};
+use crate::settings::BackendSettings;
+
+/// Extra time on top of the drain timeout for saving the chain.
+const SAVE_GRACE: Duration = Duration::from_secs(5);
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Stop the backend gracefully, blocking until it has exited.
+///
+/// Safe to call more than once; later calls return immediately.
+pub fn shutdown_backend(app: &AppHandle) {
+    let state = backend::state(app);
+    let pid = {
+        let mut state = state.lock().unwrap();
+        state.status = BackendStatus::Stopped;
+        match state.child.as_ref() {
+            Some(child) => child.pid(),
+            None => return,
+        }
+    };
+
+    log_info!("[Shell] Stopping backend (pid {})", pid);
+    let (base_url, api_token) = {
+        let state = state.lock().unwrap();
+        (state.base_url(), state.api_token.clone())
+    };
+    if let Err(e) = request_graceful_stop(app, pid, &base_url, &api_token) {
+        log_error!("[Shell] Graceful shutdown request failed: {}", e);
+        force_kill(&state);
+        return;
+    }
+
+    let deadline = Instant::now() + shutdown_timeout(app) + SAVE_GRACE;
+    while Instant::now() < deadline {
+        if state.lock().unwrap().child.is_none() {
+            log_info!("[Shell] Backend exited cleanly");
+            return;
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+
+    log_error!("[Shell] Backend did not exit in time, killing it");
+    force_kill(&state);
+}
+
+/// Stop the backend gracefully, then exit the app. Use this instead of
+/// `AppHandle::exit`, which skips straight to killing child processes.
+pub fn exit(app: &AppHandle, code: i32) {
+    shutdown_backend(app);
+    app.exit(code);
+}
+
+/// Quit from the UI, draining the backend first.
+#[tauri::command]
+pub async fn quit_app(app: AppHandle) -> Result<(), String> {
+    // The drain blocks for up to the shutdown timeout
+    tauri::async_runtime::spawn_blocking(move || exit(&app, 0))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Restart the whole app (shell and backend), draining the backend first.
+#[tauri::command]
+pub async fn relaunch_app(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        shutdown_backend(&app);
+        app.restart();
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// The `SHUTDOWN_TIMEOUT` the sidecar was given (see `settings`).
+fn shutdown_timeout(app: &AppHandle) -> Duration {
+    let secs = app
+        .try_state::<Mutex<BackendSettings>>()
+        .map(|settings| settings.lock().unwrap().shutdown.timeout_secs)
+        .unwrap_or_else(|| BackendSettings::default().shutdown.timeout_secs);
+    Duration::from_secs(secs)
+}
+
+fn force_kill(state: &Mutex<BackendState>) {
+    if let Some(child) = state.lock().unwrap().child.take() {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(unix)]
+fn request_graceful_stop(
+    _app: &AppHandle,
+    pid: u32,
+    _base_url: &str,
+    _api_token: &str,
+) -> Result<(), String> {
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    let rc = unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+#[cfg(not(unix))]
+fn request_graceful_stop(
+    app: &AppHandle,
+    _pid: u32,
+    base_url: &str,
+    api_token: &str,
+) -> Result<(), String> {
+    let client = app
+        .state::<crate::proxy::Proxy>()
+        .client()
+        .map_err(|e| e.to_string())?;
+    tauri::async_runtime::block_on(async {
+        let response = client
+            .post(format!("{}/shutdown", base_url))
+            .header(crate::auth::HEADER, api_token)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("backend answered {}", response.status()))
+        }
+    })
+}
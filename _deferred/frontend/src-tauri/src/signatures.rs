@@ -0,0 +1,274 @@
+//! Checking entry signatures without the backend.
+//!
+//! With an agent identity configured, the backend signs each entry with
+//! `identity.sign_entry_dict`: an Ed25519 signature over the compact, sorted
+//! JSON of the entry's `SIGNED_FIELDS`, stored base64-encoded with the public
+//! key in the entry's metadata. This repeats `identity.verify_entry_signature`
+//! in the shell, so the UI can tell whether a signature holds without taking
+//! the backend's word for it. Like it, only the metadata counts; the entry's
+//! own `signature` and `public_key` fields are not what gets verified.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::chainfile::{self, ChainFileError};
+use crate::encryption;
+use crate::pyjson;
+
+/// `SIGNED_FIELDS` in `src/identity.py`.
+const SIGNED_FIELDS: [&str; 4] = ["content", "author", "intent", "timestamp"];
+
+/// Same shape as `verify_entry_signature`'s result.
+#[derive(Default, serde::Serialize)]
+pub struct EntrySignature {
+    pub signed: bool,
+    pub verified: bool,
+    /// Fingerprint of the public key the entry carries.
+    pub signer: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Default, serde::Serialize)]
+pub struct AuthorCounts {
+    pub verified: usize,
+    pub invalid: usize,
+    pub unsigned: usize,
+}
+
+#[derive(serde::Serialize)]
+pub struct EntryReport {
+    /// Position of the block in the file.
+    pub block: usize,
+    /// The block's own `index` field, as stored.
+    pub block_index: Value,
+    pub entry: usize,
+    pub author: String,
+    #[serde(flatten)]
+    pub signature: EntrySignature,
+}
+
+/// Counts named as in the `/validate/chain` response's `signatures`.
+#[derive(Default, serde::Serialize)]
+pub struct SignatureReport {
+    pub path: Option<PathBuf>,
+    pub total_entries: usize,
+    pub signed: usize,
+    pub verified: usize,
+    pub unsigned: usize,
+    pub invalid: usize,
+    pub authors: BTreeMap<String, AuthorCounts>,
+    pub entries: Vec<EntryReport>,
+}
+
+/// `_canonical_entry_payload`: the signed fields that are set, as compact JSON.
+pub fn canonical_payload(entry: &Map<String, Value>) -> String {
+    let payload: Map<String, Value> = SIGNED_FIELDS
+        .iter()
+        .filter_map(|&field| {
+            let value = entry.get(field).filter(|value| !value.is_null())?;
+            Some((field.to_string(), value.clone()))
+        })
+        .collect();
+    pyjson::dumps(&Value::Object(payload), pyjson::COMPACT)
+}
+
+/// A non-empty string field from the metadata, where `sign_entry_dict` puts it.
+fn signature_field<'a>(entry: &'a Map<String, Value>, field: &str) -> Option<&'a str> {
+    entry
+        .get("metadata")
+        .and_then(|metadata| metadata.get(field))
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+}
+
+/// `AgentIdentity.fingerprint`: the first 16 hex digits of the key's SHA-256.
+pub fn fingerprint(public_key: &[u8]) -> String {
+    let mut hex = format!("{:x}", Sha256::digest(public_key));
+    hex.truncate(16);
+    hex
+}
+
+fn verify(entry: &Map<String, Value>, signature: &str, public_key: &[u8]) -> Result<(), String> {
+    let public_key: &[u8; 32] = public_key
+        .try_into()
+        .map_err(|_| "The public key is not 32 bytes long.")?;
+    let public_key = VerifyingKey::from_bytes(public_key)
+        .map_err(|_| "The public key is not a valid Ed25519 key.")?;
+    let signature = STANDARD
+        .decode(signature)
+        .map_err(|_| "The signature is not valid base64.")?;
+    let signature =
+        Signature::from_slice(&signature).map_err(|_| "The signature is not 64 bytes long.")?;
+    public_key
+        .verify(canonical_payload(entry).as_bytes(), &signature)
+        .map_err(|_| "The signature does not match the entry.".into())
+}
+
+/// Check one entry's signature, as `verify_entry_signature` does.
+pub fn check_entry(entry: &Value) -> EntrySignature {
+    let Some(entry) = entry.as_object() else {
+        return EntrySignature::default();
+    };
+    let (Some(signature), Some(public_key)) = (
+        signature_field(entry, "signature"),
+        signature_field(entry, "public_key"),
+    ) else {
+        return EntrySignature::default();
+    };
+    let Ok(key) = STANDARD.decode(public_key) else {
+        return EntrySignature {
+            signed: true,
+            error: Some("The public key is not valid base64.".into()),
+            ..EntrySignature::default()
+        };
+    };
+    let error = verify(entry, signature, &key).err();
+    EntrySignature {
+        signed: true,
+        verified: error.is_none(),
+        signer: Some(fingerprint(&key)),
+        error,
+    }
+}
+
+/// Check every entry of every block in `blocks`.
+pub fn check_chain(blocks: &[Value]) -> SignatureReport {
+    let mut report = SignatureReport::default();
+    for (position, block) in blocks.iter().enumerate() {
+        let entries = block.get("entries").and_then(Value::as_array);
+        for (i, entry) in entries.into_iter().flatten().enumerate() {
+            let signature = check_entry(entry);
+            let author = match entry.get("author") {
+                Some(Value::String(author)) => author.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            let counts = report.authors.entry(author.clone()).or_default();
+            report.total_entries += 1;
+            if signature.signed {
+                report.signed += 1;
+            }
+            if !signature.signed {
+                report.unsigned += 1;
+                counts.unsigned += 1;
+            } else if signature.verified {
+                report.verified += 1;
+                counts.verified += 1;
+            } else {
+                report.invalid += 1;
+                counts.invalid += 1;
+            }
+            report.entries.push(EntryReport {
+                block: position,
+                block_index: block.get("index").cloned().unwrap_or(Value::Null),
+                entry: i,
+                author,
+                signature,
+            });
+        }
+    }
+    report
+}
+
+fn check_file(path: &Path, key: Option<&str>) -> Result<SignatureReport, ChainFileError> {
+    let blocks = chainfile::blocks(chainfile::read(path, key)?)?;
+    let mut report = check_chain(&blocks);
+    report.path = Some(path.to_path_buf());
+    Ok(report)
+}
+
+/// Check the signature of a single entry, e.g. one the backend returned.
+#[tauri::command]
+pub fn verify_entry_signature(entry: Value) -> EntrySignature {
+    check_entry(&entry)
+}
+
+/// Check every entry signature in the chain file at `path`, or in the
+/// current workspace's saved chain, opened with `key` if it is encrypted.
+#[tauri::command]
+pub async fn verify_chain_signatures(
+    app: AppHandle,
+    path: Option<PathBuf>,
+    key: Option<String>,
+) -> Result<SignatureReport, String> {
+    let path = chainfile::locate(&app, path)?;
+    let key = encryption::key_or_default(&app, key);
+    tauri::async_runtime::spawn_blocking(move || {
+        check_file(&path, key.as_deref()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // Signed by `sign_entry_dict`; see tests/fixtures/generate.py
+    const SIGNED: &str = include_str!("../tests/fixtures/signed_entry.json");
+
+    fn signed() -> (Value, String) {
+        let fixture: Value = serde_json::from_str(SIGNED).unwrap();
+        let payload = fixture["payload"].as_str().unwrap().to_string();
+        (fixture["entry"].clone(), payload)
+    }
+
+    #[test]
+    fn canonical_payload_matches_python() {
+        let (entry, payload) = signed();
+        assert_eq!(canonical_payload(entry.as_object().unwrap()), payload);
+
+        let mut entry = entry;
+        entry["intent"] = Value::Null;
+        assert!(!canonical_payload(entry.as_object().unwrap()).contains("intent"));
+    }
+
+    #[test]
+    fn verifies_a_backend_signature() {
+        let (entry, _) = signed();
+        let result = check_entry(&entry);
+        assert!(result.signed);
+        assert!(result.verified, "{:?}", result.error);
+        assert_eq!(
+            result.signer.as_deref(),
+            entry["metadata"]["signer_fingerprint"].as_str()
+        );
+    }
+
+    #[test]
+    fn rejects_an_edited_entry() {
+        let (mut entry, _) = signed();
+        entry["content"] = json!("Carol declines to review the lease.");
+        let result = check_entry(&entry);
+        assert!(result.signed);
+        assert!(!result.verified);
+        assert!(result.error.is_some());
+
+        // Fields outside SIGNED_FIELDS may change after signing
+        let (mut entry, _) = signed();
+        entry["validation_status"] = json!("valid");
+        assert!(check_entry(&entry).verified);
+    }
+
+    #[test]
+    fn ignores_signatures_outside_the_metadata() {
+        let (mut entry, _) = signed();
+        let metadata = entry["metadata"].as_object_mut().unwrap();
+        let signature = metadata.remove("signature").unwrap();
+        let public_key = metadata.remove("public_key").unwrap();
+        entry["signature"] = signature;
+        entry["public_key"] = public_key;
+        let result = check_entry(&entry);
+        assert!(!result.signed);
+        assert!(!result.verified);
+    }
+}
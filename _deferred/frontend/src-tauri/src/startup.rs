@@ -0,0 +1,226 @@
+//! Typed startup failures and the native recovery dialog shown for them.
+//!
+//! Release builds abort on panic, so a failed sidecar start must never reach
+//! an `expect`: it ends up here instead, where the user can retry, point the
+//! shell at a backend they run themselves, or open the logs and quit.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use rfd::{MessageButtons, MessageDialog, MessageLevel};
+use tauri::{AppHandle, Manager};
+
+use crate::datadir::DataDir;
+use crate::{backend, health, logs, shutdown};
+
+#[derive(Debug)]
+pub enum StartupError {
+    /// The sidecar binary isn't where the bundle should have put it.
+    SidecarMissing(String),
+    SidecarNotExecutable(String),
+    /// No port for the sidecar, or it could not bind the one it was given.
+    PortUnavailable(String),
+    DataDirNotWritable { path: PathBuf, reason: String },
+    /// The sidecar started but exited or hung before answering `/health/ready`.
+    NotReady(String),
+    ExternalUnavailable { url: String, reason: String },
+    /// The backend speaks an API version this shell can't drive.
+    Incompatible(String),
+    /// The HTTP client for talking to the backend could not be set up.
+    HttpClient(String),
+}
+
+impl StartupError {
+    /// Short machine-readable name, for the `backend://failed` event.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StartupError::SidecarMissing(_) => "sidecar_missing",
+            StartupError::SidecarNotExecutable(_) => "sidecar_not_executable",
+            StartupError::PortUnavailable(_) => "port_unavailable",
+            StartupError::DataDirNotWritable { .. } => "data_dir_not_writable",
+            StartupError::NotReady(_) => "not_ready",
+            StartupError::ExternalUnavailable { .. } => "external_unavailable",
+            StartupError::Incompatible(_) => "incompatible",
+            StartupError::HttpClient(_) => "http_client",
+        }
+    }
+
+    fn hint(&self) -> &'static str {
+        match self {
+            StartupError::SidecarMissing(_) => {
+                "The installation looks incomplete. Reinstalling NatLangChain should restore it."
+            }
+            StartupError::SidecarNotExecutable(_) => {
+                "Check that the file may be executed and that antivirus software hasn't \
+                 quarantined it."
+            }
+            StartupError::PortUnavailable(_) => {
+                "Another program may be using the port, or a firewall is blocking loopback \
+                 connections."
+            }
+            StartupError::DataDirNotWritable { .. } => {
+                "Check the folder's permissions and that the disk isn't full or read-only."
+            }
+            StartupError::NotReady(_) => {
+                "The logs show what the backend printed before it stopped."
+            }
+            StartupError::ExternalUnavailable { .. } => {
+                "Check that the backend is running and reachable from this machine."
+            }
+            StartupError::Incompatible(_) => {
+                "Install matching versions of the app and the backend, or connect to a \
+                 backend that matches this app."
+            }
+            StartupError::HttpClient(_) => {
+                "This usually means the system's TLS or network settings could not be read."
+            }
+        }
+    }
+
+    /// Classify an error from spawning the sidecar process.
+    pub fn from_spawn(error: tauri::api::Error) -> Self {
+        match error {
+            tauri::api::Error::Io(e) if e.kind() == io::ErrorKind::NotFound => {
+                StartupError::SidecarMissing(e.to_string())
+            }
+            tauri::api::Error::Io(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                StartupError::SidecarNotExecutable(e.to_string())
+            }
+            e => StartupError::SidecarMissing(e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartupError::SidecarMissing(reason) => {
+                write!(f, "The backend program could not be found ({}).", reason)
+            }
+            StartupError::SidecarNotExecutable(reason) => {
+                write!(f, "The backend program could not be started ({}).", reason)
+            }
+            StartupError::PortUnavailable(reason) => {
+                write!(f, "No local port was available for the backend ({}).", reason)
+            }
+            StartupError::DataDirNotWritable { path, reason } => write!(
+                f,
+                "The data folder {} is not writable ({}).",
+                path.display(),
+                reason
+            ),
+            StartupError::NotReady(reason) => f.write_str(reason),
+            StartupError::ExternalUnavailable { url, reason } => {
+                write!(f, "The backend at {} is not available ({}).", url, reason)
+            }
+            StartupError::Incompatible(reason) => {
+                write!(f, "The backend is not compatible with this app ({}).", reason)
+            }
+            StartupError::HttpClient(reason) => {
+                write!(f, "The app could not set up its connection to the backend ({}).", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StartupError {}
+
+/// Make sure the data folder exists and can be written to.
+pub fn check_data_dir(app: &AppHandle) -> Result<(), StartupError> {
+    let Some(path) = app.state::<DataDir>().path() else {
+        return Ok(());
+    };
+    let probe = path.join(".write-test");
+    fs::create_dir_all(&path)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| StartupError::DataDirNotWritable {
+            path,
+            reason: e.to_string(),
+        })
+}
+
+/// Ask the user how to recover from `error`.
+///
+/// The dialog runs on the main thread, as the platform toolkits require;
+/// `rfd` at this version only has yes/no buttons, hence one question at a time.
+pub fn show_recovery_dialog(app: &AppHandle, error: &StartupError) {
+    let description = format!("{}\n\n{}\n\nTry again?", error, error.hint());
+    let handle = app.clone();
+    let result = app.run_on_main_thread(move || {
+        let app = handle;
+        let try_again = MessageDialog::new()
+            .set_level(MessageLevel::Error)
+            .set_title("NatLangChain could not start")
+            .set_description(&description)
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        if try_again {
+            retry(&app);
+            return;
+        }
+
+        // The backend URL is typed into the splash screen, so only offer
+        // this while it's still there
+        if let Some(splash) = app.get_window(health::SPLASH_WINDOW) {
+            let external = MessageDialog::new()
+                .set_level(MessageLevel::Info)
+                .set_title("NatLangChain")
+                .set_description("Connect to a NatLangChain backend you run yourself instead?")
+                .set_buttons(MessageButtons::YesNo)
+                .show();
+            if external {
+                let _ = splash.eval("window.showExternalBackendForm()");
+                let _ = splash.set_focus();
+                return;
+            }
+        }
+
+        let open_logs = MessageDialog::new()
+            .set_level(MessageLevel::Info)
+            .set_title("NatLangChain")
+            .set_description("Open the log folder before quitting?")
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        if open_logs {
+            if let Some(dir) = logs::dir() {
+                if let Err(e) = logs::reveal(dir) {
+                    log_error!("[Shell] Could not open {}: {}", dir.display(), e);
+                }
+            }
+        }
+        shutdown::exit(&app, 1);
+    });
+    if let Err(e) = result {
+        log_error!("[Shell] Could not show the startup error dialog: {}", e);
+    }
+}
+
+/// Stop whatever is left of the failed attempt and start over.
+pub fn retry(app: &AppHandle) {
+    let app = app.clone();
+    // Off the main thread: stopping a hung sidecar can take a while
+    std::thread::spawn(move || {
+        shutdown::shutdown_backend(&app);
+        backend::reset_for_retry(&app);
+        health::show_starting(&app);
+        if let Err(e) = backend::start(&app) {
+            health::fail(&app, e);
+        }
+    });
+}
+
+/// Called from the splash screen's external backend form.
+#[tauri::command]
+pub fn connect_external_backend(app: AppHandle, url: String, api_key: Option<String>) {
+    let url = url.trim().trim_end_matches('/').to_string();
+    let api_key = api_key.filter(|key| !key.is_empty());
+    std::thread::spawn(move || {
+        shutdown::shutdown_backend(&app);
+        backend::reset_for_retry(&app);
+        health::show_starting(&app);
+        tauri::async_runtime::spawn(health::connect_external(app, url, api_key, false));
+    });
+}
@@ -0,0 +1,156 @@
+//! Restarts the backend sidecar when it dies unexpectedly.
+//!
+//! Restarts back off exponentially, and the supervisor gives up once the
+//! backend has been restarted `MAX_RESTARTS` times within `RESTART_WINDOW`.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tauri::AppHandle;
+
+use crate::backend::{self, BackendState, BackendStatus};
+use crate::workspace;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_RESTARTS: usize = 5;
+const RESTART_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Called when the sidecar process exits (or could not be respawned).
+///
+/// `state` is the backend of the workspace the process belonged to, and
+/// `generation` the spawn that exited; news about a process that has
+/// already been replaced (e.g. after a retry from the startup dialog) is ignored.
+pub fn on_terminated(
+    app: &AppHandle,
+    state: &Arc<Mutex<BackendState>>,
+    generation: u64,
+    code: Option<i32>,
+) {
+    let delay = {
+        let mut state = state.lock().unwrap();
+        if state.generation != generation {
+            return;
+        }
+        state.child = None;
+        state.last_exit_code = code;
+
+        if state.status == BackendStatus::Stopped {
+            None
+        } else {
+            let delay = next_restart(&mut state.recent_restarts, Instant::now());
+            if delay.is_some() {
+                state.restart_count += 1;
+                state.status = BackendStatus::Restarting;
+            } else {
+                state.status = BackendStatus::Failed;
+            }
+            delay
+        }
+    };
+    backend::emit_status(app);
+
+    let Some(delay) = delay else {
+        return;
+    };
+    log_error!("[Shell] Restarting backend in {}s", delay.as_secs());
+
+    let app = app.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+
+        // The shell may have stopped the backend while we were waiting
+        let active = workspace::active_id(&app);
+        let workspace = {
+            let mut state = state.lock().unwrap();
+            if state.status != BackendStatus::Restarting {
+                return;
+            }
+            // Only the active workspace's sidecar runs; one switched away
+            // from stays down until it is opened again
+            if state.workspace != active {
+                state.status = BackendStatus::Stopped;
+                return;
+            }
+            state.workspace.clone()
+        };
+
+        if let Err(e) = backend::spawn_for(&app, &workspace) {
+            log_error!("[Shell] Failed to restart backend: {}", e);
+            let generation = state.lock().unwrap().generation;
+            on_terminated(&app, &state, generation, None);
+        }
+    });
+}
+
+/// How long to wait before restarting after a crash at `now`, recording the
+/// restart, or `None` if there have been too many within the window.
+fn next_restart(recent: &mut VecDeque<Instant>, now: Instant) -> Option<Duration> {
+    while let Some(&oldest) = recent.front() {
+        if now.duration_since(oldest) > RESTART_WINDOW {
+            recent.pop_front();
+        } else {
+            break;
+        }
+    }
+    if recent.len() >= MAX_RESTARTS {
+        return None;
+    }
+    let delay = backoff(recent.len());
+    recent.push_back(now);
+    Some(delay)
+}
+
+fn backoff(attempt: usize) -> Duration {
+    let factor = 1u32 << attempt.min(16);
+    INITIAL_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let delays: Vec<u64> = (0..8).map(|attempt| backoff(attempt).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30, 30]);
+        assert_eq!(backoff(usize::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn gives_up_after_too_many_crashes_in_the_window() {
+        let start = Instant::now();
+        let mut recent = VecDeque::new();
+        let delays: Vec<_> = (0..MAX_RESTARTS as u64)
+            .map(|i| next_restart(&mut recent, start + Duration::from_secs(i * 10)))
+            .collect();
+        assert_eq!(
+            delays,
+            [1, 2, 4, 8, 16].map(|secs| Some(Duration::from_secs(secs)))
+        );
+        assert_eq!(
+            next_restart(&mut recent, start + Duration::from_secs(60)),
+            None
+        );
+        // Giving up doesn't count as a restart
+        assert_eq!(recent.len(), MAX_RESTARTS);
+    }
+
+    #[test]
+    fn forgets_crashes_older_than_the_window() {
+        let start = Instant::now();
+        let mut recent = VecDeque::new();
+        for i in 0..MAX_RESTARTS as u64 {
+            next_restart(&mut recent, start + Duration::from_secs(i));
+        }
+        // The first two have aged out, so this is the fourth in the window
+        let later = start + RESTART_WINDOW + Duration::from_millis(1500);
+        assert_eq!(
+            next_restart(&mut recent, later),
+            Some(Duration::from_secs(8))
+        );
+        assert_eq!(recent.len(), 4);
+    }
+}
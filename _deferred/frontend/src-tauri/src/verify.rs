@@ -0,0 +1,338 @@
+//! Checking a chain's integrity without the backend.
+//!
+//! Runs the checks of `NatLangChain.validate_chain` on a chain file: each
+//! block's hash is recomputed the way `Block.calculate_hash` computes it, each
+//! block must name its predecessor's hash as `previous_hash`, and blocks with
+//! entries must meet the proof-of-work difficulty. Where the backend stops at
+//! the first failure with a bare `false`, this reports every block, so a
+//! tampered or corrupted file shows exactly which block and which check.
+//!
+//! Blocks and entries are normalised the way `Block.from_dict` and
+//! `NaturalLanguageEntry.from_dict` load them before hashing, so an entry
+//! stored without optional fields hashes the same as it does in the backend.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::chainfile::{self, ChainFileError};
+use crate::datadir::DataDir;
+use crate::encryption;
+use crate::pyjson;
+
+/// `validate_chain`'s default.
+pub const DEFAULT_DIFFICULTY: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    Passed,
+    Failed,
+    /// Does not apply to this block (linkage and proof of work of the
+    /// genesis block, proof of work of a block without entries).
+    Skipped,
+}
+
+#[derive(serde::Serialize)]
+pub struct BlockReport {
+    /// Position in the file, which is what linkage is checked against.
+    pub position: usize,
+    /// The block's own `index` field, as stored.
+    pub index: Value,
+    pub entry_count: usize,
+    pub stored_hash: Option<String>,
+    /// `None` if the block is too malformed to hash.
+    pub computed_hash: Option<String>,
+    pub hash: Check,
+    pub linkage: Check,
+    pub proof_of_work: Check,
+    pub valid: bool,
+    /// Why each failed check failed, in words.
+    pub problems: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ChainReport {
+    pub path: Option<PathBuf>,
+    pub valid: bool,
+    pub difficulty: usize,
+    pub block_count: usize,
+    pub invalid_blocks: usize,
+    pub blocks: Vec<BlockReport>,
+}
+
+/// Python's truthiness, which decides which optional fields are kept.
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn required(object: &Map<String, Value>, field: &str, what: &str) -> Result<Value, String> {
+    object
+        .get(field)
+        .cloned()
+        .ok_or_else(|| format!("{} has no \"{}\"", what, field))
+}
+
+/// `NaturalLanguageEntry.from_dict(entry).to_dict()`
+fn entry_dict(entry: &Value) -> Result<Value, String> {
+    let entry = entry.as_object().ok_or("an entry is not a JSON object")?;
+    let mut out = Map::new();
+    for field in ["content", "author", "intent"] {
+        out.insert(field.into(), required(entry, field, "an entry")?);
+    }
+    // `from_dict` would stamp a missing timestamp with the current time
+    let timestamp = entry
+        .get("timestamp")
+        .cloned()
+        .ok_or("an entry has no \"timestamp\", so its hash can't be reproduced")?;
+    out.insert("timestamp".into(), timestamp);
+    let metadata = entry.get("metadata").filter(|v| truthy(v));
+    out.insert("metadata".into(), metadata.cloned().unwrap_or_else(|| json!({})));
+    let status = entry.get("validation_status").cloned();
+    out.insert("validation_status".into(), status.unwrap_or_else(|| json!("pending")));
+    let paraphrases = entry.get("validation_paraphrases").cloned();
+    out.insert("validation_paraphrases".into(), paraphrases.unwrap_or_else(|| json!([])));
+    for field in ["signature", "public_key", "parent_refs", "derivative_type"] {
+        if let Some(value) = entry.get(field).filter(|v| truthy(v)) {
+            out.insert(field.into(), value.clone());
+        }
+    }
+    Ok(Value::Object(out))
+}
+
+/// What `Block.from_dict(block).calculate_hash()` serialises and hashes.
+pub fn hash_input(block: &Map<String, Value>) -> Result<Value, String> {
+    let entries = match block.get("entries") {
+        Some(Value::Array(entries)) => entries.iter().map(entry_dict).collect::<Result<_, _>>()?,
+        Some(_) => return Err("\"entries\" is not a list".into()),
+        None => return Err("the block has no \"entries\"".into()),
+    };
+    Ok(json!({
+        "index": required(block, "index", "the block")?,
+        "timestamp": required(block, "timestamp", "the block")?,
+        "entries": Value::Array(entries),
+        "previous_hash": required(block, "previous_hash", "the block")?,
+        "nonce": block.get("nonce").cloned().unwrap_or(json!(0)),
+    }))
+}
+
+/// `Block.from_dict(block).calculate_hash()`
+fn block_hash(block: &Map<String, Value>) -> Result<String, String> {
+    let data = hash_input(block)?;
+    let digest = Sha256::digest(pyjson::dumps(&data, pyjson::DEFAULT).as_bytes());
+    Ok(format!("{:x}", digest))
+}
+
+fn check_block(
+    position: usize,
+    block: &Value,
+    previous: Option<&Value>,
+    difficulty: usize,
+) -> (BlockReport, Value) {
+    let empty = Map::new();
+    let object = block.as_object().unwrap_or(&empty);
+    let mut problems = Vec::new();
+
+    let computed = if block.is_object() {
+        block_hash(object)
+    } else {
+        Err("the block is not a JSON object".into())
+    };
+    let stored = object.get("hash");
+    // `from_dict` falls back to the computed hash when none is stored
+    let effective = match (stored, &computed) {
+        (Some(hash), _) => hash.clone(),
+        (None, Ok(hash)) => Value::String(hash.clone()),
+        (None, Err(_)) => Value::Null,
+    };
+
+    let hash = match &computed {
+        Ok(hash) if effective.as_str() == Some(hash.as_str()) => Check::Passed,
+        Ok(_) => {
+            problems.push("The stored hash does not match the block's contents.".into());
+            Check::Failed
+        }
+        Err(reason) => {
+            problems.push(format!("The block can't be hashed: {}.", reason));
+            Check::Failed
+        }
+    };
+
+    let linkage = match previous {
+        None => Check::Skipped,
+        Some(previous) if object.get("previous_hash") == Some(previous) => Check::Passed,
+        Some(_) => {
+            problems.push(format!(
+                "previous_hash does not match the hash of the block at position {}.",
+                position - 1
+            ));
+            Check::Failed
+        }
+    };
+
+    let has_entries = object
+        .get("entries")
+        .and_then(Value::as_array)
+        .is_some_and(|entries| !entries.is_empty());
+    let proof_of_work = if previous.is_none() || !has_entries {
+        Check::Skipped
+    } else if effective
+        .as_str()
+        .is_some_and(|hash| hash.starts_with(&"0".repeat(difficulty)))
+    {
+        Check::Passed
+    } else {
+        problems.push(format!("The hash does not start with {} zero(s).", difficulty));
+        Check::Failed
+    };
+
+    let report = BlockReport {
+        position,
+        index: object.get("index").cloned().unwrap_or(Value::Null),
+        entry_count: object
+            .get("entries")
+            .and_then(Value::as_array)
+            .map_or(0, Vec::len),
+        stored_hash: stored.and_then(Value::as_str).map(String::from),
+        computed_hash: computed.ok(),
+        valid: ![hash, linkage, proof_of_work].contains(&Check::Failed),
+        hash,
+        linkage,
+        proof_of_work,
+        problems,
+    };
+    (report, effective)
+}
+
+/// Check every block of `blocks`, in file order.
+///
+/// Unlike `validate_chain`, the genesis block's own hash is checked too;
+/// linkage and proof of work don't apply to it.
+pub fn verify(blocks: &[Value], difficulty: usize) -> ChainReport {
+    let mut reports = Vec::with_capacity(blocks.len());
+    let mut previous = None;
+    for (position, block) in blocks.iter().enumerate() {
+        let (report, hash) = check_block(position, block, previous.as_ref(), difficulty);
+        reports.push(report);
+        previous = Some(hash);
+    }
+    let invalid_blocks = reports.iter().filter(|report| !report.valid).count();
+    ChainReport {
+        path: None,
+        valid: invalid_blocks == 0,
+        difficulty,
+        block_count: reports.len(),
+        invalid_blocks,
+        blocks: reports,
+    }
+}
+
+fn verify_file(
+    path: &Path,
+    key: Option<&str>,
+    difficulty: usize,
+) -> Result<ChainReport, ChainFileError> {
+    let blocks = chainfile::blocks(chainfile::read(path, key)?)?;
+    let mut report = verify(&blocks, difficulty);
+    report.path = Some(path.to_path_buf());
+    Ok(report)
+}
+
+/// Verify the chain file at `path`, or the current workspace's saved chain.
+/// Encrypted files are opened with `key`, or the workspace's encryption key.
+#[tauri::command]
+pub async fn verify_chain(
+    app: AppHandle,
+    path: Option<PathBuf>,
+    key: Option<String>,
+    difficulty: Option<usize>,
+) -> Result<ChainReport, String> {
+    let path = chainfile::locate(&app, path)?;
+    let key = encryption::key_or_default(&app, key);
+    let difficulty = difficulty.unwrap_or(DEFAULT_DIFFICULTY);
+    // Hashing every block of a long chain takes a while
+    tauri::async_runtime::spawn_blocking(move || {
+        verify_file(&path, key.as_deref(), difficulty).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Ask for a chain file or backup with the native picker; `None` if the
+/// user cancelled.
+#[tauri::command]
+pub async fn pick_chain_file(app: AppHandle) -> Result<Option<PathBuf>, String> {
+    let start = app.state::<DataDir>().path();
+    let (tx, rx) = mpsc::channel();
+    // Dialogs belong on the main thread
+    app.run_on_main_thread(move || {
+        let mut dialog = rfd::FileDialog::new()
+            .set_title("Choose a chain file to verify")
+            .add_filter("Chain files", &["json", "gz", "backup"]);
+        if let Some(dir) = start {
+            dialog = dialog.set_directory(dir);
+        }
+        let _ = tx.send(dialog.pick_file());
+    })
+    .map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || rx.recv().unwrap_or(None))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hashed and mined by the backend's `Block`; see tests/fixtures/generate.py
+    const CHAIN: &str = include_str!("../tests/fixtures/chain.json");
+
+    fn blocks() -> Vec<Value> {
+        let mut chain: Value = serde_json::from_str(CHAIN).unwrap();
+        serde_json::from_value(chain["chain"].take()).unwrap()
+    }
+
+    #[test]
+    fn hashes_genesis_like_the_backend() {
+        let blocks = blocks();
+        let genesis = blocks[0].as_object().unwrap();
+        assert_eq!(block_hash(genesis).unwrap(), genesis["hash"].as_str().unwrap());
+    }
+
+    #[test]
+    fn accepts_a_chain_the_backend_mined() {
+        let report = verify(&blocks(), 2);
+        let problems: Vec<_> = report.blocks.iter().map(|block| &block.problems).collect();
+        assert!(report.valid, "{:?}", problems);
+        assert_eq!(report.block_count, 2);
+        assert_eq!(report.blocks[0].proof_of_work, Check::Skipped);
+        assert_eq!(report.blocks[1].linkage, Check::Passed);
+        assert_eq!(report.blocks[1].proof_of_work, Check::Passed);
+        assert_eq!(report.blocks[1].computed_hash, report.blocks[1].stored_hash);
+    }
+
+    #[test]
+    fn reports_each_failing_block() {
+        let mut blocks = blocks();
+        blocks[0]["entries"][0]["content"] = json!("Rewritten genesis.");
+        blocks[1]["hash"] = json!("1".repeat(64));
+        let report = verify(&blocks, 2);
+        assert!(!report.valid);
+        assert_eq!(report.invalid_blocks, 2);
+        assert_eq!(report.blocks[0].hash, Check::Failed);
+        assert_eq!(report.blocks[1].hash, Check::Failed);
+        assert_eq!(report.blocks[1].proof_of_work, Check::Failed);
+        // Linkage is against the stored hash, which the edit didn't change
+        assert_eq!(report.blocks[1].linkage, Check::Passed);
+    }
+}
@@ -0,0 +1,440 @@
+//! Named workspaces: independent ledgers, each with its own settings, data
+//! folder and sidecar.
+//!
+//! The list lives in `workspaces.json` in the app config dir. The `default`
+//! workspace keeps the locations the shell used before workspaces existed
+//! (`settings.json` and `data_dir.json` in the config dir, `data/` in the
+//! data dir); any other workspace gets a `workspaces/<id>/` folder in both.
+//!
+//! Each workspace has its own `BackendState` in `backend::Backends`, but only
+//! the active one's sidecar runs: switching stops it before the next
+//! workspace's starts. With more than one workspace, the splash screen asks
+//! which one to open unless `--workspace` chose already.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::{SecondsFormat, Utc};
+use tauri::{AppHandle, Manager};
+
+use crate::backend::{self, Backends};
+use crate::datadir::{self, DataDir};
+use crate::options::LaunchOptions;
+use crate::settings::{self, BackendSettings};
+use crate::{health, shutdown};
+
+pub const DEFAULT_WORKSPACE: &str = "default";
+pub const EVENT_WORKSPACES: &str = "workspace://changed";
+
+const REGISTRY_FILE: &str = "workspaces.json";
+const WORKSPACES_DIR: &str = "workspaces";
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Workspace {
+    /// Folder name, derived from the name it was created with; never changes.
+    pub id: String,
+    pub name: String,
+    /// RFC 3339, UTC.
+    pub created: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Registry {
+    pub active: String,
+    pub workspaces: Vec<Workspace>,
+    /// Set while the splash screen waits for the user to pick a workspace;
+    /// no backend runs until they do.
+    #[serde(skip)]
+    pub awaiting_choice: bool,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_WORKSPACE.into(),
+            workspaces: vec![Workspace {
+                id: DEFAULT_WORKSPACE.into(),
+                name: "Default".into(),
+                created: now(),
+            }],
+            awaiting_choice: false,
+        }
+    }
+}
+
+impl Registry {
+    /// Read the registry and decide what to open: the workspace given with
+    /// `--workspace`, the only one there is, or (with several) none until
+    /// the user picks one on the splash screen.
+    pub fn load(app: &AppHandle, options: &LaunchOptions) -> Self {
+        let mut registry = registry_path(app)
+            .and_then(|path| match fs::read(&path) {
+                Ok(bytes) => match serde_json::from_slice::<Registry>(&bytes) {
+                    Ok(registry) => Some(registry),
+                    Err(e) => {
+                        log_error!("[Shell] Ignoring unreadable {}: {}", path.display(), e);
+                        None
+                    }
+                },
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    log_error!("[Shell] Could not read {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        if registry.find(&registry.active).is_none() {
+            registry.active = DEFAULT_WORKSPACE.into();
+        }
+
+        match &options.workspace {
+            Some(wanted) => match registry.find_by_id_or_name(wanted) {
+                Some(id) => registry.active = id,
+                None => {
+                    log_error!("[Shell] No workspace called {:?}", wanted);
+                    registry.awaiting_choice = registry.workspaces.len() > 1;
+                }
+            },
+            None => registry.awaiting_choice = registry.workspaces.len() > 1,
+        }
+        // An external backend has its own data, so there is nothing to pick
+        if options.backend_url.is_some() {
+            registry.awaiting_choice = false;
+        }
+        registry
+    }
+
+    fn find(&self, id: &str) -> Option<&Workspace> {
+        self.workspaces.iter().find(|workspace| workspace.id == id)
+    }
+
+    fn find_by_id_or_name(&self, wanted: &str) -> Option<String> {
+        self.workspaces
+            .iter()
+            .find(|workspace| workspace.id == wanted || workspace.name == wanted)
+            .map(|workspace| workspace.id.clone())
+    }
+
+    /// A folder-safe id for `name` that isn't taken yet. `on_disk` says
+    /// whether an id still has folders, as a deleted workspace's data does;
+    /// reusing it would reopen that workspace's ledger and outbox.
+    fn new_id(&self, name: &str, on_disk: impl Fn(&str) -> bool) -> String {
+        let mut slug = String::new();
+        for c in name.to_lowercase().chars() {
+            if c.is_ascii_alphanumeric() {
+                slug.push(c);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = match slug.trim_end_matches('-') {
+            "" => "workspace".to_string(),
+            slug => slug.to_string(),
+        };
+        let mut id = slug.clone();
+        let mut n = 2;
+        while self.find(&id).is_some() || on_disk(&id) {
+            id = format!("{}-{}", slug, n);
+            n += 1;
+        }
+        id
+    }
+
+    fn list(&self) -> WorkspaceList {
+        WorkspaceList {
+            active: self.active.clone(),
+            workspaces: self.workspaces.clone(),
+            awaiting_choice: self.awaiting_choice,
+        }
+    }
+}
+
+/// What the Settings view and the splash picker see.
+#[derive(Clone, serde::Serialize)]
+pub struct WorkspaceList {
+    pub active: String,
+    pub workspaces: Vec<Workspace>,
+    pub awaiting_choice: bool,
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn registry_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path_resolver()
+        .app_config_dir()
+        .map(|dir| dir.join(REGISTRY_FILE))
+}
+
+fn save(app: &AppHandle, registry: &Registry) -> io::Result<()> {
+    let path = registry_path(app)
+        .ok_or_else(|| io::Error::other("no config directory on this platform"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_vec_pretty(registry)?)?;
+    fs::rename(&temp, &path)
+}
+
+fn workspace_dir(base: PathBuf, id: &str) -> PathBuf {
+    if id == DEFAULT_WORKSPACE {
+        base
+    } else {
+        base.join(WORKSPACES_DIR).join(id)
+    }
+}
+
+/// The active workspace's id; `default` until the registry is loaded.
+pub fn active_id(app: &AppHandle) -> String {
+    app.try_state::<Mutex<Registry>>()
+        .map(|registry| registry.lock().unwrap().active.clone())
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.into())
+}
+
+/// Where the active workspace keeps its settings.
+pub fn config_dir(app: &AppHandle) -> Option<PathBuf> {
+    config_dir_of(app, &active_id(app))
+}
+
+/// Where workspace `id` keeps its settings.
+pub fn config_dir_of(app: &AppHandle, id: &str) -> Option<PathBuf> {
+    app.path_resolver()
+        .app_config_dir()
+        .map(|dir| workspace_dir(dir, id))
+}
+
+/// The folder the active workspace's default data folder goes in.
+pub fn data_root(app: &AppHandle) -> Option<PathBuf> {
+    data_root_of(app, &active_id(app))
+}
+
+/// The folder workspace `id`'s default data folder goes in.
+pub fn data_root_of(app: &AppHandle, id: &str) -> Option<PathBuf> {
+    app.path_resolver()
+        .app_data_dir()
+        .map(|dir| workspace_dir(dir, id))
+}
+
+/// Whether workspace `id`'s settings or default data folder exist.
+fn has_folders(app: &AppHandle, id: &str) -> bool {
+    [config_dir_of(app, id), data_root_of(app, id)]
+        .into_iter()
+        .flatten()
+        .any(|dir| dir.exists())
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Give the workspace a name.".into());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Workspace names can be at most {} characters.", MAX_NAME_LENGTH));
+    }
+    Ok(name.to_string())
+}
+
+/// Stop the current workspace's sidecar, make `id` active and start its own.
+fn switch(app: &AppHandle, id: &str) -> Result<(), String> {
+    if app.state::<LaunchOptions>().backend_url.is_some() {
+        return Err("Workspaces are not available while using an external backend.".into());
+    }
+    {
+        let registry = app.state::<Mutex<Registry>>();
+        let registry = registry.lock().unwrap();
+        if registry.find(id).is_none() {
+            return Err(format!("There is no workspace {:?}.", id));
+        }
+        let running = app.state::<Backends>().get(id).lock().unwrap().child.is_some();
+        if registry.active == id && !registry.awaiting_choice && running {
+            return Ok(());
+        }
+    }
+
+    shutdown::shutdown_backend(app);
+    let list = {
+        let registry = app.state::<Mutex<Registry>>();
+        let mut registry = registry.lock().unwrap();
+        registry.active = id.to_string();
+        registry.awaiting_choice = false;
+        if let Err(e) = save(app, &registry) {
+            // Still switch; next launch just starts in the old workspace
+            log_error!("[Shell] Could not save the active workspace: {}", e);
+        }
+        registry.list()
+    };
+    log_info!("[Shell] Opening workspace {}", id);
+
+    // Settings and data folder are per workspace, so reload both
+    *app.state::<Mutex<BackendSettings>>().lock().unwrap() = settings::load(app);
+    app.state::<DataDir>().reload(app);
+    let _ = app.emit_all(EVENT_WORKSPACES, list);
+
+    backend::reset_for_retry(app);
+    health::show_starting(app);
+    if let Err(e) = backend::start_sidecar(app) {
+        health::fail(app, e);
+    }
+    Ok(())
+}
+
+fn emit(app: &AppHandle) -> WorkspaceList {
+    let list = app.state::<Mutex<Registry>>().lock().unwrap().list();
+    let _ = app.emit_all(EVENT_WORKSPACES, list.clone());
+    list
+}
+
+#[tauri::command]
+pub fn list_workspaces(registry: tauri::State<'_, Mutex<Registry>>) -> WorkspaceList {
+    registry.lock().unwrap().list()
+}
+
+/// Add a workspace; it starts out empty with default settings.
+#[tauri::command]
+pub fn create_workspace(app: AppHandle, name: String) -> Result<Workspace, String> {
+    let name = validate_name(&name)?;
+    let workspace = {
+        let registry = app.state::<Mutex<Registry>>();
+        let mut registry = registry.lock().unwrap();
+        let workspace = Workspace {
+            id: registry.new_id(&name, |id| has_folders(&app, id)),
+            name,
+            created: now(),
+        };
+        registry.workspaces.push(workspace.clone());
+        if let Err(e) = save(&app, &registry) {
+            registry.workspaces.pop();
+            return Err(format!("Could not save workspaces: {}", e));
+        }
+        workspace
+    };
+    log_info!("[Shell] Created workspace {}", workspace.id);
+    emit(&app);
+    Ok(workspace)
+}
+
+#[tauri::command]
+pub fn rename_workspace(app: AppHandle, id: String, name: String) -> Result<(), String> {
+    let name = validate_name(&name)?;
+    {
+        let registry = app.state::<Mutex<Registry>>();
+        let mut registry = registry.lock().unwrap();
+        let workspace = registry
+            .workspaces
+            .iter_mut()
+            .find(|workspace| workspace.id == id)
+            .ok_or_else(|| format!("There is no workspace {:?}.", id))?;
+        let old = std::mem::replace(&mut workspace.name, name);
+        if let Err(e) = save(&app, &registry) {
+            if let Some(workspace) = registry.workspaces.iter_mut().find(|w| w.id == id) {
+                workspace.name = old;
+            }
+            return Err(format!("Could not save workspaces: {}", e));
+        }
+    }
+    emit(&app);
+    Ok(())
+}
+
+/// Forget a workspace and delete its settings. Its data folder, wherever it
+/// was moved to, is left on disk; the returned path says where, so nothing
+/// is lost by a misclick.
+#[tauri::command]
+pub fn delete_workspace(app: AppHandle, id: String) -> Result<Option<PathBuf>, String> {
+    if id == DEFAULT_WORKSPACE {
+        return Err("The default workspace can't be deleted.".into());
+    }
+    {
+        let registry = app.state::<Mutex<Registry>>();
+        let mut registry = registry.lock().unwrap();
+        if registry.active == id {
+            return Err("Switch to another workspace before deleting this one.".into());
+        }
+        let index = registry
+            .workspaces
+            .iter()
+            .position(|workspace| workspace.id == id)
+            .ok_or_else(|| format!("There is no workspace {:?}.", id))?;
+        let removed = registry.workspaces.remove(index);
+        if let Err(e) = save(&app, &registry) {
+            registry.workspaces.insert(index, removed);
+            return Err(format!("Could not save workspaces: {}", e));
+        }
+    }
+
+    app.state::<Backends>().remove(&id);
+
+    // Before its settings go, since they may say it was relocated
+    let data = datadir::path_of(&app, &id);
+    if let Some(dir) = config_dir_of(&app, &id) {
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            if e.kind() != io::ErrorKind::NotFound {
+                log_error!("[Shell] Could not remove {}: {}", dir.display(), e);
+            }
+        }
+    }
+    log_info!("[Shell] Deleted workspace {}", id);
+    emit(&app);
+    Ok(data.filter(|dir| dir.exists()))
+}
+
+/// Open workspace `id`, gracefully stopping the current one's backend first.
+/// Also answers the startup picker.
+#[tauri::command]
+pub async fn switch_workspace(app: AppHandle, id: String) -> Result<WorkspaceList, String> {
+    // Stopping the sidecar blocks until it has saved the chain
+    tauri::async_runtime::spawn_blocking(move || {
+        switch(&app, &id)?;
+        Ok(app.state::<Mutex<Registry>>().lock().unwrap().list())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(ids: &[&str]) -> Registry {
+        let mut registry = Registry::default();
+        for id in ids {
+            registry.workspaces.push(Workspace {
+                id: id.to_string(),
+                name: id.to_string(),
+                created: now(),
+            });
+        }
+        registry
+    }
+
+    #[test]
+    fn derives_ids_from_names() {
+        let registry = registry(&["ledger"]);
+        let nowhere = |_: &str| false;
+        assert_eq!(registry.new_id("Client Work", nowhere), "client-work");
+        assert_eq!(registry.new_id("  Über--Ledger! ", nowhere), "ber-ledger");
+        assert_eq!(registry.new_id("!!!", nowhere), "workspace");
+        assert_eq!(registry.new_id("Ledger", nowhere), "ledger-2");
+        assert_eq!(registry.new_id("Default", nowhere), "default-2");
+    }
+
+    #[test]
+    fn does_not_reuse_a_deleted_workspaces_folders() {
+        let base = std::env::temp_dir().join(format!("nlc-workspace-{}", std::process::id()));
+        let mut registry = registry(&["ledger"]);
+        fs::create_dir_all(workspace_dir(base.clone(), "ledger")).unwrap();
+        // Deleting forgets the workspace but leaves its data folder
+        registry
+            .workspaces
+            .retain(|workspace| workspace.id != "ledger");
+
+        let id = registry.new_id("Ledger", |id| workspace_dir(base.clone(), id).exists());
+        fs::remove_dir_all(&base).unwrap();
+        assert_eq!(id, "ledger-2");
+    }
+}
@@ -0,0 +1,60 @@
+//! Guards the capability surface exposed to the webview.
+//!
+//! The UI only needs `invoke` and `listen`, which no allowlist entry gates.
+//! Anything else goes through a typed `#[tauri::command]` in the shell, so
+//! widening the allowlist (or the matching Cargo features) should be a
+//! deliberate change to this file too.
+
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+fn read(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e))
+}
+
+fn config() -> Value {
+    serde_json::from_str(&read("tauri.conf.json")).expect("tauri.conf.json is not valid JSON")
+}
+
+#[test]
+fn allowlist_is_empty() {
+    let allowlist = &config()["tauri"]["allowlist"];
+    assert_eq!(
+        allowlist,
+        &json!({ "all": false }),
+        "the Tauri allowlist was widened; expose a typed command instead"
+    );
+}
+
+#[test]
+fn cargo_features_match_allowlist() {
+    // tauri-build rejects features the allowlist doesn't use, and vice versa,
+    // but only at build time on a full toolchain; check here as well
+    let manifest = read("Cargo.toml");
+    let tauri = manifest
+        .lines()
+        .find(|line| line.trim_start().starts_with("tauri = "))
+        .expect("no tauri dependency in Cargo.toml");
+    assert!(
+        tauri.contains(r#"features = ["process-command-api"]"#),
+        "unexpected tauri features: {}",
+        tauri
+    );
+}
+
+#[test]
+fn csp_only_connects_to_self() {
+    let csp = config()["tauri"]["security"]["csp"]
+        .as_str()
+        .expect("no CSP configured")
+        .to_string();
+    let connect_src = csp
+        .split(';')
+        .map(str::trim)
+        .find(|directive| directive.starts_with("connect-src"))
+        .expect("CSP has no connect-src");
+    let sources: Vec<&str> = connect_src.split_whitespace().skip(1).collect();
+    assert_eq!(sources, ["'self'"]);
+}
@@ -0,0 +1,169 @@
+"""
+Writes the fixtures the shell's unit tests compare against, using the
+backend's own modules so the two can't drift apart unnoticed.
+
+Run from anywhere; the files are written next to this script:
+
+    python3 _deferred/frontend/src-tauri/tests/fixtures/generate.py
+"""
+
+import json
+import os
+import struct
+import sys
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+sys.path.insert(0, os.path.join(HERE, "..", "..", "..", "..", "..", "src"))
+
+from cryptography.hazmat.primitives.asymmetric.ed25519 import Ed25519PrivateKey  # noqa: E402
+
+import encryption  # noqa: E402
+from blockchain import Block, NaturalLanguageEntry  # noqa: E402
+from identity import AgentIdentity, _canonical_entry_payload, sign_entry_dict  # noqa: E402
+from storage.json_file import JSONFileStorage  # noqa: E402
+
+# Fixed, so the expected values in the tests stay put
+SEED = bytes(range(32))
+PASSPHRASE = "fixture passphrase"
+ENCRYPTION_KEY = "fixture encryption key"
+
+
+def write(name, data):
+    mode = "wb" if isinstance(data, bytes) else "w"
+    with open(os.path.join(HERE, name), mode) as f:
+        f.write(data)
+
+
+def fixture_identity():
+    private_key = Ed25519PrivateKey.from_private_bytes(SEED)
+    return AgentIdentity("fixture", private_key, private_key.public_key())
+
+
+def identities():
+    identity = fixture_identity()
+    identity.save(os.path.join(HERE, "identity.pem"))
+    identity.save(os.path.join(HERE, "identity_encrypted.pem"), passphrase=PASSPHRASE)
+    write("identity.pub", identity.public_key_b64 + "\n")
+
+
+def signatures():
+    entry = NaturalLanguageEntry(
+        content="Carol agrees to review the caf\u00e9 lease \U0001F4DD by Monday.",
+        author="carol",
+        intent="Agree to review the lease",
+        metadata={"topic": "lease"},
+    )
+    entry.timestamp = "2025-01-02T09:30:00.000001"
+    entry_dict = entry.to_dict()
+    payload = _canonical_entry_payload(entry_dict).decode("utf-8")
+    sign_entry_dict(entry_dict, fixture_identity())
+    write("signed_entry.json", json.dumps({"entry": entry_dict, "payload": payload}, indent=2) + "\n")
+
+
+def pyjson():
+    floats = [
+        0.0, -0.0, 0.1, 1 / 3, -1.5, 100.0, 1e-4, 1e-5, 2.5e-7, 1e15, 1e16,
+        123456789012345680.0, 5e-324, 1.7976931348623157e308,
+        float("nan"), float("inf"), float("-inf"),
+    ]
+    values = [
+        {"b": 1, "a": [1.5, 1e16, 5e-324, -0.0], "Z": None, "\u00e9": True, "\U0001F600": "x"},
+        {"nested": {"z": [], "a": {}, "m": [{"y": 2, "x": 1}]}},
+        "non-BMP \U0001F600 \U0001D11E",
+        "non-ASCII \u00e9 \u4e2d\u6587 \u2028",
+        "controls \x00\x1f\x7f \" \\ \n\t\b\f\r",
+        [12345678901234567890, -9223372036854775808, 0, False],
+    ]
+    cases = {
+        "floats": [
+            {"bits": struct.unpack("<Q", struct.pack("<d", f))[0], "repr": json.dumps(f)}
+            for f in floats
+        ],
+        "values": [
+            {
+                "value": value,
+                "default": json.dumps(value, sort_keys=True),
+                "compact": json.dumps(value, sort_keys=True, separators=(",", ":")),
+                "unicode": json.dumps(value, sort_keys=True, ensure_ascii=False),
+            }
+            for value in values
+        ],
+    }
+    write("pyjson.json", json.dumps(cases, indent=2) + "\n")
+
+
+def mine(block, difficulty):
+    """What `NatLangChain.mine_pending_entries` does to a new block."""
+    while not block.hash.startswith("0" * difficulty):
+        block.nonce += 1
+        block.hash = block.calculate_hash()
+
+
+def chain():
+    genesis_entry = NaturalLanguageEntry(
+        content="This is the genesis block of the fixture chain.",
+        author="system",
+        intent="Initialize the NatLangChain",
+        metadata={"type": "genesis"},
+    )
+    genesis_entry.timestamp = "2025-01-01T00:00:00"
+    genesis_entry.validation_status = "validated"
+    genesis = Block(index=0, entries=[genesis_entry], previous_hash="0")
+    genesis.timestamp = 1735689600.0
+    genesis.hash = genesis.calculate_hash()
+
+    offer = NaturalLanguageEntry(
+        content="Alice offers to deliver 12.5 kg of coffee \U0001F30D by Friday.",
+        author="alice",
+        intent="Offer coffee delivery",
+        metadata={"amount": 12.5, "scale": 1e16, "tags": ["caf\u00e9", "\U0001D11E"]},
+    )
+    offer.timestamp = "2025-01-01T00:01:00.123456"
+    offer.validation_status = "valid"
+    offer.validation_paraphrases = ["Alice will deliver coffee."]
+    accept = NaturalLanguageEntry(
+        content="Bob accepts Alice's offer.",
+        author="bob",
+        intent="Accept the offer",
+        parent_refs=[{"block_index": 1, "entry_index": 0}],
+        derivative_type="response",
+    )
+    accept.timestamp = "2025-01-01T00:02:00"
+    block = Block(index=1, entries=[offer, accept], previous_hash=genesis.hash)
+    block.timestamp = 1735689720.5
+    block.hash = block.calculate_hash()
+    mine(block, 2)
+
+    blocks = [genesis.to_dict(), block.to_dict()]
+    write("chain.json", json.dumps({"chain": blocks}, indent=2) + "\n")
+    return blocks
+
+
+def encrypted(blocks):
+    os.environ[encryption.ENCRYPTION_ENABLED_ENV] = "true"
+    os.environ[encryption.ENCRYPTION_KEY_ENV] = ENCRYPTION_KEY
+
+    metadata = {
+        "topic": "lease",
+        "private_notes": "Don't go below 1000 \U0001F4B0",
+        "payment_amount": {"amount": 1250.5, "currency": "EUR"},
+    }
+    cases = {
+        "key": ENCRYPTION_KEY,
+        "plain": metadata,
+        "encrypted": encryption.encrypt_sensitive_fields(metadata, ENCRYPTION_KEY),
+    }
+    write("encrypted_metadata.json", json.dumps(cases, indent=2) + "\n")
+
+    # Gzipped, then encrypted, as the backend saves it by default with a key set
+    path = os.path.join(HERE, "chain_encrypted.dat")
+    JSONFileStorage(path, encryption_enabled=True, compression_enabled=True).save_chain(
+        {"chain": blocks}
+    )
+
+
+if __name__ == "__main__":
+    identities()
+    signatures()
+    pyjson()
+    encrypted(chain())
@@ -3,16 +3,473 @@
  * Handles all communication with the Flask backend
  */
 
-// In development, Vite proxies /api to localhost:5000
-// In production (Tauri), we need to call the Flask server directly
+import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
+
+// Running inside the Tauri shell (as opposed to a plain browser)
+export const isTauri = typeof window !== 'undefined' && '__TAURI_IPC__' in window;
+
+// In a browser during development, Vite proxies /api to localhost:5000.
+// Inside Tauri, every call goes through the shell's `api_request` command,
+// which forwards it to the backend sidecar with the credentials attached.
 const isDev = import.meta.env.DEV;
-const API_BASE = isDev ? '/api' : 'http://localhost:5000';
+const BROWSER_API_BASE = isDev ? '/api' : 'http://localhost:5000';
+
+/**
+ * Subscribe to requests the backend rejected for a missing or wrong API key.
+ * Returns an unlisten function (as a promise).
+ */
+export function onAuthRejected(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('backend://auth-rejected', (event) => callback(event.payload));
+}
+
+/**
+ * Subscribe to launches of the app that were folded into this window instead
+ * of starting a second instance: { args, cwd }.
+ * Returns an unlisten function (as a promise).
+ */
+export function onSecondInstance(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('app://second-instance', (event) => callback(event.payload));
+}
+
+let backendReady = null;
+
+/**
+ * Resolves once the Tauri shell reports the backend sidecar as ready.
+ * Resolves immediately in a plain browser.
+ */
+export function whenBackendReady() {
+  if (!isTauri) return Promise.resolve();
+  if (!backendReady) {
+    backendReady = new Promise((resolve) => {
+      // Subscribe before asking so a ready event in between isn't missed
+      listen('backend://ready', () => resolve());
+      invoke('backend_ready').then((ready) => {
+        if (ready) resolve();
+      });
+    });
+  }
+  return backendReady;
+}
+
+/**
+ * Current sidecar status from the Tauri shell:
+ * { status, url, external, pid, restart_count, last_exit_code }
+ */
+export async function getBackendStatus() {
+  if (!isTauri) {
+    return {
+      status: 'ready',
+      url: BROWSER_API_BASE,
+      external: true,
+      pid: null,
+      restart_count: 0,
+      last_exit_code: null,
+    };
+  }
+  return invoke('backend_status');
+}
+
+/**
+ * Result of the shell's version handshake with the backend, or null before
+ * the first one (and outside Tauri):
+ * { shell_version, backend_version, api_version, disabled_features, warnings }
+ */
+export async function getBackendCompat() {
+  if (!isTauri) return null;
+  return invoke('backend_compat');
+}
+
+/**
+ * Subscribe to handshake results (one per backend start).
+ * Returns an unlisten function (as a promise).
+ */
+export function onBackendCompat(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('backend://compat', (event) => callback(event.payload));
+}
+
+/**
+ * Subscribe to sidecar status changes. Returns an unlisten function (as a promise).
+ */
+export function onBackendStatus(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('backend://status', (event) => callback(event.payload));
+}
+
+/**
+ * Open the shell's log directory (backend.log, shell.log) in the file manager.
+ */
+export async function openLogDirectory() {
+  if (!isTauri) throw new Error('Log files are only available in the desktop app');
+  return invoke('open_log_dir');
+}
+
+/**
+ * Last `lines` records of a log file ('backend' or 'shell'), oldest first:
+ * [{ timestamp, stream, pid, message }]
+ */
+export async function tailLog(file = 'backend', lines = 200) {
+  if (!isTauri) return [];
+  return invoke('tail_log', { file, lines });
+}
+
+/**
+ * Recent backend sidecar output buffered by the shell, oldest first:
+ * [{ seq, timestamp, stream, pid, level, logger, message, data }]
+ */
+export async function getBackendLogs(limit = null) {
+  if (!isTauri) return [];
+  return invoke('recent_backend_logs', { limit });
+}
+
+/**
+ * Subscribe to backend output lines. The shell only emits them while the
+ * stream is switched on with setBackendLogStream(true).
+ * Returns an unlisten function (as a promise).
+ */
+export function onBackendLog(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('backend://log', (event) => callback(event.payload));
+}
+
+export async function setBackendLogStream(enabled) {
+  if (!isTauri) return;
+  return invoke('set_backend_log_stream', { enabled });
+}
+
+/**
+ * Backend configuration kept by the shell and passed to the sidecar:
+ * { llm, storage, encryption, identity, security, rate_limits, logging, shutdown }.
+ * Stored secrets come back as '********'; send that back to keep them.
+ */
+export async function getBackendSettings() {
+  if (!isTauri) return null;
+  return invoke('get_backend_settings');
+}
+
+/**
+ * Save backend settings. The shell restarts the sidecar if they change its
+ * environment. Resolves to the saved settings; rejects with a message if
+ * they are invalid.
+ */
+export async function updateBackendSettings(settings) {
+  if (!isTauri) throw new Error('Backend settings are only available in the desktop app');
+  return invoke('update_backend_settings', { settings });
+}
+
+export async function resetBackendSettings() {
+  if (!isTauri) throw new Error('Backend settings are only available in the desktop app');
+  return invoke('reset_backend_settings');
+}
+
+/**
+ * The folder holding the chain file and its backups:
+ * { path, default_path, is_default, chain_exists, size }
+ */
+export async function getDataDirInfo() {
+  if (!isTauri) return null;
+  return invoke('data_dir_info');
+}
+
+export async function revealDataDir() {
+  if (!isTauri) throw new Error('The data folder is only available in the desktop app');
+  return invoke('reveal_data_dir');
+}
+
+/**
+ * Show a native folder picker. Resolves to the chosen path, or null if cancelled.
+ */
+export async function pickDataDir() {
+  if (!isTauri) return null;
+  return invoke('pick_data_dir');
+}
+
+/**
+ * Move the chain and its backups to `path`; the backend restarts on the new
+ * folder. Resolves to the new data folder info.
+ */
+export async function relocateDataDir(path) {
+  if (!isTauri) throw new Error('The data folder is only available in the desktop app');
+  return invoke('relocate_data_dir', { path });
+}
+
+/**
+ * Move the chain back to the default data folder.
+ */
+export async function resetDataDir() {
+  if (!isTauri) throw new Error('The data folder is only available in the desktop app');
+  return invoke('reset_data_dir');
+}
+
+/**
+ * Workspaces known to the shell, each with its own chain, settings and backend:
+ * { active, workspaces: [{ id, name, created }], awaiting_choice }
+ */
+export async function listWorkspaces() {
+  if (!isTauri) return null;
+  return invoke('list_workspaces');
+}
+
+/**
+ * Subscribe to workspace list and active workspace changes.
+ * Returns an unlisten function (as a promise).
+ */
+export function onWorkspacesChanged(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('workspace://changed', (event) => callback(event.payload));
+}
+
+export async function createWorkspace(name) {
+  if (!isTauri) throw new Error('Workspaces are only available in the desktop app');
+  return invoke('create_workspace', { name });
+}
+
+export async function renameWorkspace(id, name) {
+  if (!isTauri) throw new Error('Workspaces are only available in the desktop app');
+  return invoke('rename_workspace', { id, name });
+}
+
+/**
+ * Forget a workspace that isn't active. Its data folder stays on disk;
+ * resolves to its path (or null if it had none).
+ */
+export async function deleteWorkspace(id) {
+  if (!isTauri) throw new Error('Workspaces are only available in the desktop app');
+  return invoke('delete_workspace', { id });
+}
+
+/**
+ * Stop the current workspace's backend and start workspace `id`'s.
+ * Resolves to the updated workspace list.
+ */
+export async function switchWorkspace(id) {
+  if (!isTauri) throw new Error('Workspaces are only available in the desktop app');
+  return invoke('switch_workspace', { id });
+}
+
+/**
+ * Check a chain file's hashes, linkage and proof of work in the shell, without
+ * the backend. `path` defaults to the current workspace's saved chain; an
+ * encrypted file needs `key` unless it is the workspace's own encryption key.
+ * Resolves to { path, valid, difficulty, block_count, invalid_blocks, blocks },
+ * where each block has { position, index, entry_count, stored_hash,
+ * computed_hash, hash, linkage, proof_of_work, valid, problems } and each check
+ * is 'passed', 'failed' or 'skipped'.
+ */
+export async function verifyChainLocally(path = null, difficulty = null, key = null) {
+  if (!isTauri) throw new Error('Offline verification is only available in the desktop app');
+  return invoke('verify_chain', { path, key, difficulty });
+}
+
+/**
+ * Check an entry's Ed25519 signature in the shell, as the backend's
+ * verify_entry_signature does. Resolves to { signed, verified, signer, error },
+ * where `signer` is the fingerprint of the entry's public key.
+ */
+export async function verifyEntrySignature(entry) {
+  if (!isTauri) return null;
+  return invoke('verify_entry_signature', { entry });
+}
+
+/**
+ * Check every entry signature in a chain file (default: the current
+ * workspace's saved chain). Resolves to { path, total_entries, signed,
+ * verified, unsigned, invalid, authors: { [author]: { verified, invalid,
+ * unsigned } }, entries: [{ block, block_index, entry, author, signed,
+ * verified, signer, error }] }.
+ */
+export async function verifyChainSignatures(path = null, key = null) {
+  if (!isTauri) throw new Error('Offline verification is only available in the desktop app');
+  return invoke('verify_chain_signatures', { path, key });
+}
+
+/**
+ * Read a chain file (default: the current workspace's saved chain) in the
+ * shell: plain, gzip-compressed or encrypted with `key` (default: the
+ * workspace's encryption key). Resolves to the file's JSON document, usually
+ * { chain: [...blocks], pending_entries, ... }. Sensitive metadata fields stay
+ * encrypted until passed to decryptMetadata.
+ */
+export async function openChainFile(path = null, key = null) {
+  if (!isTauri) throw new Error('Chain files are only available in the desktop app');
+  return invoke('open_chain_file', { path, key });
+}
+
+/**
+ * Decrypt the `ENC:1:` fields of an entry's metadata with `key` (default:
+ * the workspace's encryption key). Resolves to { metadata, failed }: as in
+ * the backend, fields the key doesn't fit keep their encrypted value, and
+ * `failed` names them.
+ */
+export async function decryptMetadata(metadata, key = null) {
+  if (!isTauri) throw new Error('Decryption is only available in the desktop app');
+  return invoke('decrypt_metadata', { metadata, key });
+}
+
+/**
+ * Show a native file picker for a chain file or backup. Resolves to the chosen
+ * path, or null if cancelled.
+ */
+export async function pickChainFile() {
+  if (!isTauri) return null;
+  return invoke('pick_chain_file');
+}
+
+/**
+ * Agent identities in the shell's keystore:
+ * [{ id, name, fingerprint, public_key, created, unlocked }].
+ * Keys are stored encrypted with their passphrase.
+ */
+export async function listIdentities() {
+  if (!isTauri) return [];
+  return invoke('list_identities');
+}
+
+/**
+ * Create a new Ed25519 key protected by `passphrase` (at least 16
+ * characters). It stays unlocked until the app quits.
+ */
+export async function generateIdentity(name, passphrase) {
+  if (!isTauri) throw new Error('Identities are only available in the desktop app');
+  return invoke('generate_identity', { name, passphrase });
+}
+
+/**
+ * Import a key file saved by the backend's AgentIdentity. `filePassphrase`
+ * is the file's own passphrase, if it has one; `passphrase` protects the key
+ * in the keystore. The name defaults to the file name.
+ */
+export async function importIdentity(path, filePassphrase, name, passphrase) {
+  if (!isTauri) throw new Error('Identities are only available in the desktop app');
+  return invoke('import_identity', { path, filePassphrase, name, passphrase });
+}
+
+/**
+ * Write an identity's key to `path` in the format AgentIdentity.load reads,
+ * encrypted with `filePassphrase` if one is given.
+ */
+export async function exportIdentity(id, passphrase, path, filePassphrase = null) {
+  if (!isTauri) throw new Error('Identities are only available in the desktop app');
+  return invoke('export_identity', { id, passphrase, path, filePassphrase });
+}
+
+export async function deleteIdentity(id) {
+  if (!isTauri) throw new Error('Identities are only available in the desktop app');
+  return invoke('delete_identity', { id });
+}
+
+export async function unlockIdentity(id, passphrase) {
+  if (!isTauri) throw new Error('Identities are only available in the desktop app');
+  return invoke('unlock_identity', { id, passphrase });
+}
+
+export async function lockIdentity(id) {
+  if (!isTauri) return;
+  return invoke('lock_identity', { id });
+}
+
+/**
+ * Sign { content, author, intent, metadata } with an unlocked identity.
+ * Resolves to the entry with its timestamp and signature fields set, ready
+ * for submitEntry.
+ */
+export async function signEntry(id, entry) {
+  if (!isTauri) throw new Error('Identities are only available in the desktop app');
+  return invoke('sign_entry', { id, entry });
+}
+
+/**
+ * Show a native picker for a key file to import, or (with `save`) to export
+ * to. Resolves to the chosen path, or null if cancelled.
+ */
+export async function pickIdentityFile(save = false) {
+  if (!isTauri) return null;
+  return invoke('pick_identity_file', { save });
+}
+
+/**
+ * Search for a proof-of-work nonce on every core. `block` has the fields
+ * Block.calculate_hash hashes: { index, timestamp, entries, previous_hash,
+ * nonce }. Resolves to { block, nonce, hash, difficulty, hashes, elapsed_ms }
+ * with `block` completed; rejects when cancelled or after `timeoutSecs`
+ * (default 300).
+ */
+export async function mineBlockLocally(block, difficulty = 2, timeoutSecs = null) {
+  if (!isTauri) throw new Error('Local mining is only available in the desktop app');
+  return invoke('mine_block', { block, difficulty, timeoutSecs }).catch((e) => {
+    throw new Error(e);
+  });
+}
+
+/**
+ * Subscribe to mining progress: { hashes, hashes_per_second, nonce, elapsed_ms }.
+ * Returns an unlisten function (as a promise).
+ */
+export function onMiningProgress(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('mine://progress', (event) => callback(event.payload));
+}
+
+/**
+ * Stop the local search in progress. Resolves to whether there was one.
+ */
+export async function cancelMining() {
+  if (!isTauri) return false;
+  return invoke('cancel_mining');
+}
+
+// Backend states in which chain reads come from the saved chain file
+const OFFLINE_STATUSES = ['restarting', 'stopped', 'failed'];
+
+/**
+ * Whether the backend is down, so chain reads are answered by the shell from
+ * the saved chain file. Always false in a plain browser.
+ */
+export async function isOffline() {
+  if (!isTauri) return false;
+  const { status } = await getBackendStatus();
+  return OFFLINE_STATUSES.includes(status);
+}
+
+/**
+ * Read `endpoint` from the backend, or from the saved chain with the shell's
+ * `command` while the backend is down. Offline responses have the same shape
+ * plus `offline: true`, and are read-only.
+ */
+async function readChain(endpoint, command, args = {}) {
+  if (!(await isOffline())) return fetchAPI(endpoint);
+  return invoke(command, args).catch((e) => {
+    throw new Error(e);
+  });
+}
+
+/**
+ * `fetch` through the shell's `api_request` command, resolving to a
+ * `Response` like the real one would.
+ */
+async function shellFetch(endpoint, { method = 'GET', headers = {}, body } = {}) {
+  const response = await invoke('api_request', {
+    method,
+    path: endpoint,
+    body: body ?? null,
+    contentType: headers['Content-Type'] ?? null,
+  });
+  // These statuses may not carry a body, even an empty one
+  const empty = [204, 205, 304].includes(response.status);
+  return new Response(empty ? null : response.body, {
+    status: response.status,
+    headers: response.headers,
+  });
+}
 
 /**
  * Generic fetch wrapper with error handling
  */
 async function fetchAPI(endpoint, options = {}) {
-  const url = `${API_BASE}${endpoint}`;
+  await whenBackendReady();
 
   const defaultHeaders = {
     'Content-Type': 'application/json',
@@ -27,7 +484,9 @@ async function fetchAPI(endpoint, options = {}) {
   };
 
   try {
-    const response = await fetch(url, config);
+    const response = isTauri
+      ? await shellFetch(endpoint, config)
+      : await fetch(`${BROWSER_API_BASE}${endpoint}`, config);
     const data = await response.json();
 
     if (!response.ok) {
@@ -46,32 +505,162 @@ async function fetchAPI(endpoint, options = {}) {
 // ============================================================
 
 export async function getChainInfo() {
-  return fetchAPI('/chain');
+  return readChain('/chain', 'get_chain');
 }
 
 export async function getBlock(index) {
-  return fetchAPI(`/block/${index}`);
+  return readChain(`/block/${index}`, 'get_block', { index });
 }
 
 export async function getLatestBlock() {
-  return fetchAPI('/block/latest');
+  return readChain('/block/latest', 'get_latest_block');
+}
+
+export async function getEntriesByAuthor(author) {
+  return readChain(`/entries/author/${encodeURIComponent(author)}`, 'get_entries_by_author', {
+    author,
+  });
+}
+
+/**
+ * Entries whose intent contains `intent`, ignoring case, as
+ * NatLangChain.get_entries_by_intent finds them. The backend has no endpoint
+ * for this, so the shell always reads it from the saved chain.
+ */
+export async function getEntriesByIntent(intent) {
+  if (!isTauri) throw new Error('Intent lookup is only available in the desktop app');
+  return invoke('get_entries_by_intent', { intent });
 }
 
 export async function validateChain() {
   return fetchAPI('/validate/chain');
 }
 
+// ============================================================
+// Local Chain Index (desktop app)
+// ============================================================
+
+/**
+ * State of the shell's SQLite index of the chain:
+ * { blocks, entries, tip, synced_at, syncing, error }.
+ */
+export async function getChainIndexStatus() {
+  if (!isTauri) return null;
+  return invoke('chain_index_status');
+}
+
+/**
+ * Fetch the blocks the index doesn't have yet. Resolves to its status.
+ * With `fromScratch`, the index is thrown away and rebuilt.
+ */
+export async function syncChainIndex(fromScratch = false) {
+  if (!isTauri) throw new Error('The chain index is only available in the desktop app');
+  return invoke(fromScratch ? 'resync_chain_index' : 'sync_chain_index');
+}
+
+/**
+ * A page of indexed blocks, as getBlock returns them, newest first unless
+ * `newestFirst` is false. Resolves to { total, offset, items }.
+ */
+export async function listIndexedBlocks(offset = 0, limit = 50, newestFirst = true) {
+  if (!isTauri) throw new Error('The chain index is only available in the desktop app');
+  return invoke('list_indexed_blocks', { offset, limit, newestFirst });
+}
+
+/**
+ * A page of indexed entries, newest first, matching `filter`: { author,
+ * intent (contained, ignoring case), derivative_type, since, until }, where
+ * `since` and `until` are ISO dates and `until` is exclusive. Resolves to
+ * { total, offset, items } with items { block_index, entry_index,
+ * block_hash, entry }.
+ */
+export async function queryIndexedEntries(filter = {}, offset = 0, limit = 50) {
+  if (!isTauri) throw new Error('The chain index is only available in the desktop app');
+  return invoke('query_indexed_entries', { filter, offset, limit });
+}
+
+/**
+ * Subscribe to index changes; the callback gets its status, as
+ * getChainIndexStatus returns it. Returns an unlisten function (as a promise).
+ */
+export function onChainIndexSynced(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('index://synced', (event) => callback(event.payload));
+}
+
 // ============================================================
 // Entry Operations
 // ============================================================
 
-export async function submitEntry(content, author, intent, metadata = {}) {
+/**
+ * `timestamp` is only needed for entries signed by the client, whose
+ * signature covers it.
+ */
+export async function submitEntry(content, author, intent, metadata = {}, timestamp = null) {
+  const entry = { content, author, intent, metadata };
+  if (timestamp) entry.timestamp = timestamp;
   return fetchAPI('/entry', {
     method: 'POST',
-    body: JSON.stringify({ content, author, intent, metadata }),
+    body: JSON.stringify(entry),
   });
 }
 
+/**
+ * Submit `entry` ({ content, author, intent, metadata, timestamp? }) through
+ * the shell's outbox, which keeps it on disk until the backend accepts it.
+ * `signedWith` is the identity that signed it, if any. Resolves to
+ * { status: 'delivered', response } or { status: 'queued', item } when the
+ * backend is unavailable or rate limits it; rejects if the backend refuses
+ * the entry for any other reason.
+ */
+export async function submitEntryQueued(entry, signedWith = null) {
+  if (!isTauri) throw new Error('The outbox is only available in the desktop app');
+  return invoke('submit_entry', { entry, signedWith });
+}
+
+/**
+ * Entries waiting in the outbox: { id, entry, signed_with, queued_at, status
+ * ('queued' or 'held'), attempts, next_attempt, last_error, rejection }, where
+ * `rejection` is { kind, status, reason, message, retry_after, details } and
+ * `kind` is 'rate_limit', 'duplicate', 'quality', 'timestamp' or 'invalid'.
+ */
+export async function listOutbox() {
+  if (!isTauri) return [];
+  return invoke('list_outbox');
+}
+
+/**
+ * Replace a queued entry's content, author, intent and metadata and queue it
+ * again. A signed entry is signed again, so its identity must be unlocked.
+ */
+export async function updateOutboxEntry(id, entry) {
+  if (!isTauri) throw new Error('The outbox is only available in the desktop app');
+  return invoke('update_outbox_entry', { id, entry });
+}
+
+/**
+ * Send a queued or held entry now. Resolves like submitEntryQueued, except
+ * that a rejected entry stays in the outbox.
+ */
+export async function retryOutboxEntry(id) {
+  if (!isTauri) throw new Error('The outbox is only available in the desktop app');
+  return invoke('retry_outbox_entry', { id });
+}
+
+export async function discardOutboxEntry(id) {
+  if (!isTauri) throw new Error('The outbox is only available in the desktop app');
+  return invoke('discard_outbox_entry', { id });
+}
+
+/**
+ * Subscribe to outbox changes; the callback gets the whole list, as
+ * listOutbox returns it. Returns an unlisten function (as a promise).
+ */
+export function onOutboxChanged(callback) {
+  if (!isTauri) return Promise.resolve(() => {});
+  return listen('outbox://changed', (event) => callback(event.payload));
+}
+
 export async function getPendingEntries() {
   return fetchAPI('/pending');
 }
@@ -94,6 +683,25 @@ export async function mineBlock(minerId = 'web-miner') {
   });
 }
 
+/**
+ * The block mining the pending entries would produce, for `mineBlockLocally`.
+ */
+export async function getMiningTemplate() {
+  return fetchAPI('/mine/template');
+}
+
+/**
+ * Hand the backend a template whose nonce was found locally. It answers as
+ * `mineBlock` does, or rejects (409) if the chain or pending entries have
+ * moved on since the template was taken.
+ */
+export async function submitMinedBlock(block, difficulty = 2) {
+  return fetchAPI('/mine/submit', {
+    method: 'POST',
+    body: JSON.stringify({ block, difficulty }),
+  });
+}
+
 // ============================================================
 // Search Operations
 // ============================================================
@@ -110,6 +718,17 @@ export async function semanticSearch(query, limit = 10) {
   });
 }
 
+/**
+ * Full-text search of the saved chain in the shell, which works without the
+ * backend. Matches are ranked by BM25 on word stems; the query can quote
+ * phrases and use `author:`, `intent:`, `content:` and `metadata:`. Each
+ * result has `highlights`: per matching field, { text, highlight } segments.
+ */
+export async function searchEntriesLocal(query, limit = 10) {
+  if (!isTauri) throw new Error('Local search is only available in the desktop app');
+  return invoke('search_entries_local', { query, limit });
+}
+
 // ============================================================
 // Contract Operations
 // ============================================================
@@ -173,7 +792,7 @@ export async function verifyEvent(condition, intent, event, eventData = {}) {
 // ============================================================
 
 export async function getStats() {
-  return fetchAPI('/stats');
+  return readChain('/stats', 'get_stats');
 }
 
 // ============================================================
@@ -143,6 +143,10 @@ export const ncipDefinitions = {
     text: "Uses AI to find entries with similar meaning. Works best with natural language queries describing what you're looking for.",
     ncipRef: 'NCIP-002',
   },
+  localSearch: {
+    text: 'Ranks entries by their words in the saved chain, without the backend. Quote phrases, or narrow with author:, intent:, content: or metadata:.',
+    ncipRef: 'NCIP-001',
+  },
 
   // Chain Status
   chainValid: {
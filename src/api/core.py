@@ -80,6 +80,7 @@ def add_entry():
         "author": "identifier of the creator",
         "intent": "brief summary of purpose",
         "metadata": {} (optional),
+        "timestamp": "ISO 8601 UTC time" (optional, for entries signed by the client),
         "validate": true/false (optional, default true),
         "auto_mine": true/false (optional, default false)
     }
@@ -97,6 +98,7 @@ def add_entry():
         "content": 50000,  # 50KB max for content
         "author": 500,  # 500 chars for author
         "intent": 2000,  # 2KB for intent
+        "timestamp": 64,
     }
 
     is_valid, error_msg = validate_json_schema(
@@ -104,6 +106,7 @@ def add_entry():
         required_fields={"content": str, "author": str, "intent": str},
         optional_fields={
             "metadata": dict,
+            "timestamp": str,
             "validate": bool,
             "auto_mine": bool,
             "validation_mode": str,
@@ -161,9 +164,16 @@ def add_entry():
             return jsonify({"error": "Invalid metadata format"}), 400
 
     # Create entry with encrypted sensitive metadata
-    entry = create_entry_with_encryption(
-        content=content, author=author, intent=intent, metadata=data.get("metadata", {})
-    )
+    try:
+        entry = create_entry_with_encryption(
+            content=content,
+            author=author,
+            intent=intent,
+            metadata=data.get("metadata", {}),
+            timestamp=data.get("timestamp"),
+        )
+    except ValueError as e:
+        return jsonify({"error": str(e)}), 400
 
     # Validate if requested
     validate = data.get("validate", True)
@@ -320,6 +330,70 @@ def mine_block():
     ), 201
 
 
+@core_bp.route("/mine/template", methods=["GET"])
+@require_api_key
+def get_mining_template():
+    """
+    Get the block mining the pending entries would produce, for a client
+    (such as the desktop shell) to search for its nonce.
+
+    Returns:
+        The candidate block, with nonce 0
+    """
+    template = state.blockchain.mining_template()
+    if template is None:
+        return jsonify({"error": "No pending entries to mine"}), 400
+
+    return jsonify({"block": template})
+
+
+@core_bp.route("/mine/submit", methods=["POST"])
+@require_api_key
+def submit_mined_block():
+    """
+    Append a block from /mine/template whose nonce the client has found.
+
+    Request body:
+    {
+        "block": {...} (the template with its nonce filled in),
+        "difficulty": 2 (optional, default from chain)
+    }
+
+    Returns:
+        Mined block details, or 409 if the chain or pending entries have
+        moved on since the template was taken
+    """
+    data = request.get_json() or {}
+    block = data.get("block")
+    if not isinstance(block, dict):
+        return jsonify({"error": "Missing required field: block"}), 400
+
+    difficulty = data.get("difficulty")
+    try:
+        if difficulty:
+            new_block = state.blockchain.submit_mined_block(block, difficulty=difficulty)
+        else:
+            new_block = state.blockchain.submit_mined_block(block)
+    except ValueError as e:
+        return jsonify({"error": str(e)}), 409
+
+    # Persist to file
+    save_chain()
+
+    return jsonify(
+        {
+            "status": "success",
+            "block": {
+                "index": new_block.index,
+                "timestamp": new_block.timestamp,
+                "entries_count": len(new_block.entries),
+                "hash": new_block.hash,
+                "previous_hash": new_block.previous_hash,
+            },
+        }
+    ), 201
+
+
 @core_bp.route("/block/<int:index>", methods=["GET"])
 @require_api_key
 def get_block(index: int):
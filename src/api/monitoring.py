@@ -7,16 +7,20 @@ This blueprint provides:
 - /health: Basic health check
 - /health/live: Kubernetes liveness probe
 - /health/ready: Kubernetes readiness probe
+- /version: Version and capability handshake for the desktop shell
+- /shutdown: Graceful shutdown trigger for the desktop shell
 - /cluster/instances: List active instances
 - /cluster/info: Cluster coordination info
 """
 
 import os
 import platform
+import signal
 import sys
+import threading
 import time
 
-from flask import Blueprint, Response, jsonify
+from flask import Blueprint, Response, jsonify, request
 
 from . import state
 from .state import get_storage
@@ -28,6 +32,9 @@ monitoring_bp = Blueprint("monitoring", __name__)
 # Track startup time
 _startup_time = time.time()
 
+# Bump when an endpoint the desktop shell or UI relies on changes incompatibly
+API_VERSION = 1
+
 
 @monitoring_bp.route("/metrics", methods=["GET"])
 def prometheus_metrics():
@@ -141,6 +148,32 @@ def readiness():
     return jsonify({"status": "ready"})
 
 
+@monitoring_bp.route("/version", methods=["GET"])
+def version_info():
+    """
+    Version and capability handshake.
+
+    Lets clients such as the desktop shell check that they speak the same
+    API and find out which optional features this process has enabled.
+    """
+    from api.utils import managers
+
+    return jsonify(
+        {
+            "service": "NatLangChain API",
+            "version": _get_version(),
+            "api_version": API_VERSION,
+            "capabilities": {
+                "llm_validation": managers.llm_validator is not None,
+                "semantic_search": managers.search_engine is not None,
+                "contracts": managers.contract_parser is not None,
+                "identity_signing": state.agent_identity is not None,
+                "shutdown": True,
+            },
+        }
+    )
+
+
 @monitoring_bp.route("/health/detailed", methods=["GET"])
 @_require_api_key
 def detailed_health():
@@ -187,6 +220,30 @@ def detailed_health():
     )
 
 
+@monitoring_bp.route("/shutdown", methods=["POST"])
+@_require_api_key
+def shutdown():
+    """
+    Request a graceful shutdown.
+
+    Used by the desktop shell on platforms where it cannot deliver SIGTERM
+    (Windows). Raises SIGTERM in-process so the same drain-and-save handler
+    registered by run_server.py runs. Only accepted from loopback.
+    """
+    if request.remote_addr not in ("127.0.0.1", "::1"):
+        return jsonify({"error": "Shutdown is only allowed from localhost"}), 403
+
+    if not state.is_shutting_down():
+        _request_shutdown()
+
+    return jsonify({"status": "shutting_down"}), 202
+
+
+def _request_shutdown():
+    """Raise SIGTERM shortly after this response has been sent."""
+    threading.Timer(0.1, signal.raise_signal, args=(signal.SIGTERM,)).start()
+
+
 def _get_version() -> str:
     """Get application version."""
     try:
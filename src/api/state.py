@@ -123,19 +123,31 @@ except ImportError:
 
 
 def create_entry_with_encryption(
-    content: str, author: str, intent: str, metadata: dict[str, Any] | None = None
+    content: str,
+    author: str,
+    intent: str,
+    metadata: dict[str, Any] | None = None,
+    timestamp: str | None = None,
 ) -> Any:
     """
     Create an entry with optional encryption of sensitive metadata fields.
 
+    If the metadata already carries a signature and public key (the entry was
+    signed by the client, e.g. with the desktop app's keystore), that signature
+    is kept instead of signing with the server's identity, provided it verifies.
+
     Args:
         content: Entry content
         author: Entry author
         intent: Entry intent
         metadata: Optional metadata dictionary
+        timestamp: Optional ISO 8601 UTC timestamp the client signed over
 
     Returns:
         NaturalLanguageEntry with encrypted sensitive fields if encryption enabled
+
+    Raises:
+        ValueError: If a client signature is present but does not verify
     """
     from blockchain import NaturalLanguageEntry
 
@@ -149,6 +161,18 @@ def create_entry_with_encryption(
     entry = NaturalLanguageEntry(
         content=content, author=author, intent=intent, metadata=encrypted_metadata
     )
+    if timestamp:
+        entry.timestamp = timestamp
+
+    # Keep a client signature rather than replacing it with the server's
+    if entry.metadata.get("signature") and entry.metadata.get("public_key"):
+        from identity import verify_entry_signature
+
+        if not verify_entry_signature(entry.to_dict())["verified"]:
+            raise ValueError("Entry signature does not verify")
+        entry.signature = entry.metadata["signature"]
+        entry.public_key = entry.metadata["public_key"]
+        return entry
 
     # Sign entry with cryptographic identity if available (Audit 1.3)
     if agent_identity is not None:
@@ -2110,40 +2110,118 @@ class NatLangChain:
                 new_block.hash = new_block.calculate_hash()
 
             self.chain.append(new_block)
+            self._record_mined_entries(new_block)
+            self.pending_entries = []
 
-            # Complete any pending asset transfers for mined entries
-            if self.enable_asset_tracking and self._asset_registry is not None:
-                for entry in self.pending_entries:
-                    transfer_info = self._detect_asset_transfer(entry)
-                    if transfer_info["is_transfer"]:
-                        fingerprint = compute_entry_fingerprint(
-                            entry.content, entry.author, entry.intent
-                        )
-                        self._asset_registry.complete_transfer(
-                            asset_id=transfer_info["asset_id"], fingerprint=fingerprint
-                        )
-
-            # Register derivative relationships for mined entries
-            if self.enable_derivative_tracking and self._derivative_registry is not None:
-                block_index = new_block.index
-                for entry_index, entry in enumerate(self.pending_entries):
-                    if entry.is_derivative() and entry.parent_refs:
-                        self._derivative_registry.register_derivative(
-                            child_block=block_index,
-                            child_entry=entry_index,
-                            parent_refs=entry.parent_refs,
-                            derivative_type=entry.derivative_type or DERIVATIVE_TYPE_REFERENCE,
-                            child_metadata={
-                                "author": entry.author,
-                                "intent": entry.intent,
-                                "timestamp": entry.timestamp,
-                            },
-                        )
+            return new_block
 
-            self.pending_entries = []
+    def mining_template(self) -> dict[str, Any] | None:
+        """
+        Get the block that mining the pending entries now would produce.
+
+        Lets a client search for the nonce itself and hand the result to
+        submit_mined_block.
+
+        Returns:
+            The candidate block's dictionary with nonce 0, or None if no
+            pending entries
+        """
+        with self._mining_lock:
+            if not self.pending_entries:
+                return None
+
+            return Block(
+                index=len(self.chain),
+                entries=self.pending_entries.copy(),
+                previous_hash=self.get_latest_block().hash,
+            ).to_dict()
+
+    def submit_mined_block(self, block_data: dict[str, Any], difficulty: int = 2) -> Block:
+        """
+        Append a block from mining_template whose nonce a client has found.
+
+        The block is rebuilt from this chain's own pending entries, so the
+        client only chooses the nonce; entries added to the pool since the
+        template was taken stay pending.
+
+        Args:
+            block_data: The template with its nonce filled in
+            difficulty: Number of leading zeros required in hash
+
+        Returns:
+            The appended block
+
+        Raises:
+            ValueError: If the block no longer extends the chain, its entries
+                are not the pending ones, or its hash misses the difficulty
+        """
+        with self._mining_lock:
+            if block_data.get("index") != len(self.chain) or (
+                block_data.get("previous_hash") != self.get_latest_block().hash
+            ):
+                raise ValueError("The block does not extend the current chain")
+
+            timestamp = block_data.get("timestamp")
+            nonce = block_data.get("nonce")
+            if not isinstance(timestamp, (int, float)) or not isinstance(nonce, int):
+                raise ValueError("The block needs a numeric timestamp and an integer nonce")
+            # A template is stamped after the latest block and before now
+            if not self.get_latest_block().timestamp <= timestamp <= time.time():
+                raise ValueError("The block's timestamp is not the template's")
+
+            count = len(block_data.get("entries") or [])
+            entries = self.pending_entries[:count]
+            if not count or [entry.to_dict() for entry in entries] != block_data["entries"]:
+                raise ValueError("The block's entries are not the pending entries")
+
+            new_block = Block(
+                index=len(self.chain),
+                entries=entries,
+                previous_hash=self.get_latest_block().hash,
+                nonce=nonce,
+            )
+            new_block.timestamp = timestamp
+            new_block.hash = new_block.calculate_hash()
+            if not new_block.hash.startswith("0" * difficulty):
+                raise ValueError(f"The block's hash does not start with {difficulty} zeros")
+
+            self.chain.append(new_block)
+            self._record_mined_entries(new_block)
+            self.pending_entries = self.pending_entries[count:]
 
             return new_block
 
+    def _record_mined_entries(self, block: Block) -> None:
+        """Update the asset and derivative registries for a newly mined block."""
+        # Complete any pending asset transfers for mined entries
+        if self.enable_asset_tracking and self._asset_registry is not None:
+            for entry in block.entries:
+                transfer_info = self._detect_asset_transfer(entry)
+                if transfer_info["is_transfer"]:
+                    fingerprint = compute_entry_fingerprint(
+                        entry.content, entry.author, entry.intent
+                    )
+                    self._asset_registry.complete_transfer(
+                        asset_id=transfer_info["asset_id"], fingerprint=fingerprint
+                    )
+
+        # Register derivative relationships for mined entries
+        if self.enable_derivative_tracking and self._derivative_registry is not None:
+            block_index = block.index
+            for entry_index, entry in enumerate(block.entries):
+                if entry.is_derivative() and entry.parent_refs:
+                    self._derivative_registry.register_derivative(
+                        child_block=block_index,
+                        child_entry=entry_index,
+                        parent_refs=entry.parent_refs,
+                        derivative_type=entry.derivative_type or DERIVATIVE_TYPE_REFERENCE,
+                        child_metadata={
+                            "author": entry.author,
+                            "intent": entry.intent,
+                            "timestamp": entry.timestamp,
+                        },
+                    )
+
     def validate_chain(self, verify_pow: bool = True, difficulty: int = 1) -> bool:
         """
         Validate the entire blockchain for integrity.
@@ -181,6 +181,7 @@ class AgentIdentity:
         Returns:
             True if signature is valid, False otherwise
         """
+        from cryptography.exceptions import InvalidSignature
         from cryptography.hazmat.primitives.asymmetric.ed25519 import (
             Ed25519PublicKey,
         )
@@ -191,7 +192,7 @@ class AgentIdentity:
             payload = _canonical_entry_payload(entry_dict)
             public_key.verify(signature, payload)
             return True
-        except (ValueError, OSError) as e:
+        except (InvalidSignature, ValueError, OSError) as e:
             logger.warning("Signature verification failed: %s", e)
             return False
 
@@ -31,6 +31,51 @@ class TestHealthEndpoints:
         assert "version" in data
 
 
+class TestVersionEndpoint:
+    """Tests for the version and capability handshake."""
+
+    def test_version_reports_api_version(self, flask_client):
+        """Should report the API version the shell checks against."""
+        from api import monitoring
+
+        response = flask_client.get("/version")
+        assert response.status_code == 200
+        data = json.loads(response.data)
+        assert data["api_version"] == monitoring.API_VERSION
+        assert "version" in data
+
+    def test_version_reports_capabilities(self, flask_client):
+        """Should report which optional features are enabled."""
+        response = flask_client.get("/version")
+        capabilities = json.loads(response.data)["capabilities"]
+        assert set(capabilities) >= {"llm_validation", "semantic_search", "contracts"}
+        assert all(isinstance(value, bool) for value in capabilities.values())
+
+
+class TestShutdownEndpoint:
+    """Tests for the desktop shell's graceful shutdown endpoint."""
+
+    def test_shutdown_accepted_from_loopback(self, flask_client, monkeypatch):
+        """Loopback callers should trigger a shutdown request."""
+        from api import monitoring
+
+        calls = []
+        monkeypatch.setattr(monitoring, "_request_shutdown", lambda: calls.append(True))
+        response = flask_client.post("/shutdown")
+        assert response.status_code == 202
+        assert calls == [True]
+
+    def test_shutdown_rejected_from_remote(self, flask_client, monkeypatch):
+        """Non-loopback callers must not be able to stop the server."""
+        from api import monitoring
+
+        calls = []
+        monkeypatch.setattr(monitoring, "_request_shutdown", lambda: calls.append(True))
+        response = flask_client.post("/shutdown", environ_base={"REMOTE_ADDR": "10.0.0.5"})
+        assert response.status_code == 403
+        assert calls == []
+
+
 class TestEntryCreation:
     """Tests for entry creation endpoint."""
 
@@ -92,6 +137,59 @@ class TestEntryCreation:
         )
         assert response.status_code == 400
 
+    def test_create_entry_keeps_client_signature(self, flask_client, test_auth_headers):
+        """Should keep a valid signature made by the client over its timestamp."""
+        from datetime import datetime
+
+        from identity import AgentIdentity, sign_entry_dict
+
+        identity = AgentIdentity.generate("desktop")
+        payload = sign_entry_dict(
+            {
+                "content": "Party A agrees to deliver the signed report to Party B by Friday.",
+                "author": "test-author",
+                "intent": "Delivery commitment",
+                "timestamp": datetime.utcnow().isoformat(),
+            },
+            identity,
+        )
+        response = flask_client.post(
+            "/entry",
+            data=json.dumps(payload),
+            headers=test_auth_headers,
+        )
+        assert response.status_code == 201
+        # The response itself may have the key redacted by secret scanning
+        from api import state
+
+        entry = state.blockchain.pending_entries[-1]
+        assert entry.timestamp == payload["timestamp"]
+        assert entry.signature == payload["metadata"]["signature"]
+        assert entry.public_key == identity.public_key_b64
+
+    def test_create_entry_rejects_bad_client_signature(self, flask_client, test_auth_headers):
+        """Should reject a client signature that does not match the entry."""
+        from datetime import datetime
+
+        from identity import AgentIdentity, sign_entry_dict
+
+        payload = sign_entry_dict(
+            {
+                "content": "Party A agrees to deliver the signed report to Party B by Friday.",
+                "author": "test-author",
+                "intent": "Delivery commitment",
+                "timestamp": datetime.utcnow().isoformat(),
+            },
+            AgentIdentity.generate("desktop"),
+        )
+        payload["content"] = payload["content"].replace("Friday", "Monday")
+        response = flask_client.post(
+            "/entry",
+            data=json.dumps(payload),
+            headers=test_auth_headers,
+        )
+        assert response.status_code == 400
+
 
 class TestChainEndpoints:
     """Tests for chain retrieval endpoints."""
@@ -142,6 +240,78 @@ class TestMiningEndpoints:
         assert response.status_code in [200, 201, 400]
 
 
+class TestClientMiningEndpoints:
+    """Tests for mining with the nonce searched by the client."""
+
+    @staticmethod
+    def _add_pending():
+        from api import state
+        from blockchain import NaturalLanguageEntry
+
+        state.blockchain.pending_entries.append(
+            NaturalLanguageEntry(content="Entry to be mined", author="miner-test", intent="Mining")
+        )
+
+    @staticmethod
+    def _solve(template, difficulty):
+        from blockchain import Block
+
+        block = Block.from_dict(template)
+        while not block.calculate_hash().startswith("0" * difficulty):
+            block.nonce += 1
+        return {**template, "nonce": block.nonce}
+
+    def test_template_and_submit(self, flask_client, test_auth_headers, monkeypatch):
+        """A solved template should be appended to the chain."""
+        from api import core, state
+
+        monkeypatch.setattr(core, "save_chain", lambda: None)
+        self._add_pending()
+        response = flask_client.get("/mine/template", headers=test_auth_headers)
+        assert response.status_code == 200
+        template = json.loads(response.data)["block"]
+        assert template["nonce"] == 0
+
+        response = flask_client.post(
+            "/mine/submit",
+            data=json.dumps({"block": self._solve(template, 2), "difficulty": 2}),
+            headers=test_auth_headers,
+        )
+        assert response.status_code == 201
+        mined = json.loads(response.data)["block"]
+        assert mined["index"] == template["index"]
+        assert mined["hash"].startswith("00")
+        assert state.blockchain.chain[-1].hash == mined["hash"]
+        assert state.blockchain.pending_entries == []
+
+    def test_submit_stale_block_conflicts(self, flask_client, test_auth_headers, monkeypatch):
+        """A block for a chain that has moved on should be refused."""
+        from api import core, state
+
+        monkeypatch.setattr(core, "save_chain", lambda: None)
+        self._add_pending()
+        template = json.loads(
+            flask_client.get("/mine/template", headers=test_auth_headers).data
+        )["block"]
+        state.blockchain.mine_pending_entries(difficulty=1)
+        self._add_pending()
+
+        response = flask_client.post(
+            "/mine/submit",
+            data=json.dumps({"block": self._solve(template, 1), "difficulty": 1}),
+            headers=test_auth_headers,
+        )
+        assert response.status_code == 409
+        assert "error" in json.loads(response.data)
+
+    def test_submit_requires_block(self, flask_client, test_auth_headers):
+        """Should reject a submission without a block."""
+        response = flask_client.post(
+            "/mine/submit", data=json.dumps({}), headers=test_auth_headers
+        )
+        assert response.status_code == 400
+
+
 class TestBlockRetrieval:
     """Tests for block retrieval endpoints."""
 
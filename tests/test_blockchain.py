@@ -243,6 +243,66 @@ class TestMining:
         assert len(chain.chain) == 3  # Genesis + 2 mined
 
 
+class TestClientMining:
+    @staticmethod
+    def _solve(template, difficulty):
+        """Search for the nonce as a client would."""
+        block = Block.from_dict(template)
+        while not block.calculate_hash().startswith("0" * difficulty):
+            block.nonce += 1
+        return {**template, "nonce": block.nonce}
+
+    def test_template_empty_returns_none(self):
+        chain = _make_chain()
+        assert chain.mining_template() is None
+
+    def test_submit_appends_block(self):
+        chain = _make_chain()
+        chain.add_entry(_make_entry())
+        block = chain.submit_mined_block(self._solve(chain.mining_template(), 2), difficulty=2)
+        assert chain.chain[-1] is block
+        assert block.hash.startswith("00")
+        assert chain.pending_entries == []
+        assert chain.validate_chain(difficulty=2)
+
+    def test_submit_keeps_entries_added_since_template(self):
+        chain = _make_chain()
+        chain.add_entry(_make_entry(content="Entry A"))
+        template = chain.mining_template()
+        chain.add_entry(_make_entry(content="Entry B"))
+        block = chain.submit_mined_block(self._solve(template, 1), difficulty=1)
+        assert [e.content for e in block.entries] == ["Entry A"]
+        assert [e.content for e in chain.pending_entries] == ["Entry B"]
+
+    def test_submit_rejects_insufficient_work(self):
+        chain = _make_chain()
+        chain.add_entry(_make_entry())
+        solved = self._solve(chain.mining_template(), 1)
+        while Block.from_dict(solved).calculate_hash().startswith("0"):
+            solved["nonce"] += 1
+        with pytest.raises(ValueError, match="zeros"):
+            chain.submit_mined_block(solved, difficulty=1)
+        assert len(chain.chain) == 1
+
+    def test_submit_rejects_stale_template(self):
+        chain = _make_chain()
+        chain.add_entry(_make_entry())
+        template = chain.mining_template()
+        chain.mine_pending_entries(difficulty=1)
+        chain.add_entry(_make_entry())
+        with pytest.raises(ValueError, match="extend"):
+            chain.submit_mined_block(self._solve(template, 1), difficulty=1)
+
+    def test_submit_rejects_other_entries(self):
+        chain = _make_chain()
+        chain.add_entry(_make_entry(content="Pending"))
+        template = chain.mining_template()
+        template["entries"][0]["content"] = "Something else"
+        with pytest.raises(ValueError, match="pending entries"):
+            chain.submit_mined_block(self._solve(template, 1), difficulty=1)
+        assert len(chain.pending_entries) == 1
+
+
 # ============================================================
 # Chain Validation
 # ============================================================